@@ -963,7 +963,7 @@ build_app_center() {
     # ── Source-hash cache: skip build if source hasn't changed ──
     local bin_cache="/var/cache/smplos/binaries"
     local src_hash
-    src_hash=$({ find "$ac_src/src" "$ac_src/ui" -type f -exec sha256sum {} + 2>/dev/null; \
+    src_hash=$({ find "$ac_src/src" "$ac_src/ui" "$SRC_DIR/editions" -type f -exec sha256sum {} + 2>/dev/null; \
         sha256sum "$ac_src/Cargo.toml" "$ac_src/Cargo.lock" "$ac_src/build.rs" 2>/dev/null; \
     } | sort | sha256sum | cut -d' ' -f1)
     local cache_key="app-center-${src_hash}"
@@ -972,6 +972,7 @@ build_app_center() {
         log_info "app-center source unchanged, using cached binary ($cache_key)"
         install -Dm755 "$bin_cache/$cache_key" "$airootfs/usr/local/bin/app-center"
         install -Dm755 "$bin_cache/$cache_key" "$airootfs/root/smplos/bin/app-center"
+        install -Dm644 "$ac_src/app-center.desktop" "$airootfs/usr/share/applications/app-center.desktop"
         return 0
     fi
 
@@ -986,7 +987,8 @@ build_app_center() {
     cd "$build_dir"
 
     log_info "Compiling app-center (release)..."
-    cargo build --release
+    # Edition package lists are baked in by build.rs (bundles view)
+    SMPLOS_EDITIONS_DIR="$SRC_DIR/editions" cargo build --release
 
     local bin_path="$build_dir/target/release/app-center"
     if [[ ! -x "$bin_path" ]]; then
@@ -1004,6 +1006,9 @@ build_app_center() {
     install -Dm755 "$bin_path" "$airootfs/root/smplos/bin/app-center"
     strip "$airootfs/root/smplos/bin/app-center"
 
+    # Desktop entry registers app-center as the appstream:// link handler
+    install -Dm644 "$ac_src/app-center.desktop" "$airootfs/usr/share/applications/app-center.desktop"
+
     # Save to cache for future builds
     mkdir -p "$bin_cache"
     cp "$airootfs/usr/local/bin/app-center" "$bin_cache/$cache_key"
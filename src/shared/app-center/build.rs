@@ -1,3 +1,49 @@
+use std::path::PathBuf;
+
 fn main() {
     slint_build::compile("ui/main.slint").expect("failed to compile Slint UI");
+    embed_editions();
+}
+
+/// Bake every edition under the editions directory into the binary as
+/// `(id, description.txt, packages-extra.txt, packages-aur-extra.txt)`
+/// tuples, sorted by id. ISO builds compile a copy of this crate outside
+/// the source tree, so the builder points SMPLOS_EDITIONS_DIR at
+/// `src/editions`.
+fn embed_editions() {
+    println!("cargo:rerun-if-env-changed=SMPLOS_EDITIONS_DIR");
+    let dir = std::env::var("SMPLOS_EDITIONS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("../../editions")
+        });
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut ids: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+
+    let mut out = String::from("&[\n");
+    for id in &ids {
+        let read = |file: &str| {
+            let path = dir.join(id).join(file);
+            println!("cargo:rerun-if-changed={}", path.display());
+            std::fs::read_to_string(path).unwrap_or_default()
+        };
+        let info = read("description.txt");
+        let repo = read("packages-extra.txt");
+        let aur = read("packages-aur-extra.txt");
+        out.push_str(&format!("    ({:?}, {:?}, {:?}, {:?}),\n", id, info, repo, aur));
+    }
+    out.push(']');
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("editions.rs"), out).expect("failed to write editions.rs");
 }
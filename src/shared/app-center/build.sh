@@ -80,6 +80,8 @@ action="${1:-}"
 if [[ "$action" == "--install" ]]; then
   echo "==> Installing to /usr/local/bin/app-center"
   sudo install -Dm755 "$BIN_PATH" /usr/local/bin/app-center
+  sudo install -Dm644 "$SCRIPT_DIR/app-center.desktop" /usr/share/applications/app-center.desktop
+  xdg-mime default app-center.desktop x-scheme-handler/appstream 2>/dev/null || true
   echo "Installed: /usr/local/bin/app-center"
 elif [[ "$action" == "--run" ]]; then
   echo "==> Running app-center"
@@ -0,0 +1,210 @@
+use crate::catalog::{cache_dir, cache_is_fresh, read_cache, write_cache};
+use crate::vercmp::vercmp;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const FEED_MAX_AGE: Duration = Duration::from_secs(3600 * 6);
+pub const DEFAULT_FEED: &str = "https://security.archlinux.org/issues/all.json";
+
+/// One advisory group (AVG) from the Arch security tracker.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdvisoryGroup {
+    pub name: String,
+    pub packages: Vec<String>,
+    pub status: String,
+    pub severity: String,
+    pub affected: String,
+    pub fixed: Option<String>,
+    pub issues: Vec<String>,
+}
+
+/// An installed package that is affected by one or more advisory groups.
+#[derive(Clone, Debug)]
+pub struct Vulnerability {
+    pub package: String,
+    pub installed: String,
+    /// Highest severity among the matching groups.
+    pub severity: String,
+    pub cves: Vec<String>,
+    /// Lowest version that fixes every matching group, if one exists.
+    pub fixed: Option<String>,
+}
+
+/// Load the tracker feed. `source` is a URL or a local file path; URLs
+/// are cached and re-downloaded when stale, falling back to the cache.
+pub fn load(source: &str) -> Vec<AdvisoryGroup> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return std::fs::read_to_string(source)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+    }
+
+    let cache_path = cache_dir().join("advisories.json");
+    if cache_is_fresh(&cache_path, FEED_MAX_AGE) {
+        if let Some(cached) = read_cache::<Vec<AdvisoryGroup>>(&cache_path) {
+            return cached;
+        }
+    }
+
+    match download(source) {
+        Some(groups) => {
+            write_cache(&cache_path, &groups);
+            groups
+        }
+        None => read_cache::<Vec<AdvisoryGroup>>(&cache_path).unwrap_or_default(),
+    }
+}
+
+fn download(url: &str) -> Option<Vec<AdvisoryGroup>> {
+    let resp = ureq::get(url)
+        .timeout(Duration::from_secs(15))
+        .call()
+        .ok()?;
+    resp.into_json().ok()
+}
+
+/// Whether `installed` is affected by a group: below the fixed version, or
+/// any version while no fix has been released. `affected` is only the
+/// version current when the issue was reported, not a lower bound, so held
+/// back and downgraded packages still count (as with arch-audit).
+fn is_affected(group: &AdvisoryGroup, installed: &str) -> bool {
+    if group.status == "Not affected" {
+        return false;
+    }
+    match &group.fixed {
+        Some(fixed) => vercmp(installed, fixed) == Ordering::Less,
+        None => true,
+    }
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "Critical" => 4,
+        "High" => 3,
+        "Medium" => 2,
+        "Low" => 1,
+        _ => 0,
+    }
+}
+
+/// Match advisory groups against installed package versions
+/// (see `catalog::installed_pacman_versions`). Sorted by severity.
+pub fn match_installed(
+    groups: &[AdvisoryGroup],
+    installed: &HashMap<String, String>,
+) -> Vec<Vulnerability> {
+    let mut found: HashMap<String, Vulnerability> = HashMap::new();
+    for group in groups {
+        for package in &group.packages {
+            let Some(version) = installed.get(package) else { continue };
+            if !is_affected(group, version) {
+                continue;
+            }
+            let vuln = found.entry(package.clone()).or_insert_with(|| Vulnerability {
+                package: package.clone(),
+                installed: version.clone(),
+                severity: String::new(),
+                cves: Vec::new(),
+                fixed: group.fixed.clone(),
+            });
+            if severity_rank(&group.severity) > severity_rank(&vuln.severity) {
+                vuln.severity = group.severity.clone();
+            }
+            for cve in &group.issues {
+                if !vuln.cves.contains(cve) {
+                    vuln.cves.push(cve.clone());
+                }
+            }
+            // Every matching group must be fixed for the package to be safe.
+            vuln.fixed = match (&vuln.fixed, &group.fixed) {
+                (Some(a), Some(b)) => Some(if vercmp(a, b) == Ordering::Less { b } else { a }.clone()),
+                _ => None,
+            };
+        }
+    }
+
+    let mut list: Vec<Vulnerability> = found.into_values().collect();
+    list.sort_by(|a, b| {
+        severity_rank(&b.severity)
+            .cmp(&severity_rank(&a.severity))
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    list
+}
+
+/// Where to read the feed from: `--advisories <url|file>` on the command
+/// line, otherwise the public tracker.
+pub fn feed_source(args: &[String]) -> String {
+    args.iter()
+        .position(|a| a == "--advisories")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_FEED.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Local stand-in for the tracker feed.
+    fn feed() -> Vec<AdvisoryGroup> {
+        load(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/advisories.json"))
+    }
+
+    fn installed(pkgs: &[(&str, &str)]) -> HashMap<String, String> {
+        pkgs.iter().map(|(p, v)| (p.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn loads_local_feed() {
+        let groups = feed();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[1].fixed.as_deref(), Some("1:3.2.1-1"));
+        assert_eq!(groups[2].fixed, None);
+    }
+
+    #[test]
+    fn missing_feed_file_is_empty() {
+        assert!(load("/nonexistent/advisories.json").is_empty());
+    }
+
+    #[test]
+    fn merges_groups_per_package() {
+        let found = match_installed(&feed(), &installed(&[("openssl", "3.1.4-1")]));
+        assert_eq!(found.len(), 1);
+        let vuln = &found[0];
+        assert_eq!(vuln.severity, "Critical");
+        assert_eq!(vuln.cves, ["CVE-2024-0727", "CVE-2024-2511"]);
+        // The epoch makes 1:3.2.1-1 the fix for both groups.
+        assert_eq!(vuln.fixed.as_deref(), Some("1:3.2.1-1"));
+    }
+
+    #[test]
+    fn versions_below_affected_are_flagged() {
+        // Held back or downgraded below the reported version.
+        let found = match_installed(&feed(), &installed(&[("openssl", "3.0.2-1")]));
+        assert_eq!(found.len(), 1);
+        let found = match_installed(&feed(), &installed(&[("vim", "8.2.5000-1")]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].fixed, None);
+    }
+
+    #[test]
+    fn fixed_and_not_affected_are_skipped() {
+        let found = match_installed(
+            &feed(),
+            &installed(&[("openssl", "1:3.2.1-1"), ("lib32-openssl", "1:3.3.0-1"), ("curl", "8.5.0-1")]),
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn sorted_by_severity() {
+        let found = match_installed(&feed(), &installed(&[("vim", "9.1.0-2"), ("lib32-openssl", "3.2.0-1")]));
+        let names: Vec<&str> = found.iter().map(|v| v.package.as_str()).collect();
+        assert_eq!(names, ["lib32-openssl", "vim"]);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
@@ -7,6 +8,8 @@ use std::time::{Duration, SystemTime};
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Source {
     Aur,
+    /// Installed packages from the official repositories.
+    Repo,
     Flatpak,
     AppImage,
 }
@@ -15,6 +18,7 @@ impl Source {
     pub fn label(&self) -> &'static str {
         match self {
             Source::Aur => "AUR",
+            Source::Repo => "Repo",
             Source::Flatpak => "Flatpak",
             Source::AppImage => "AppImage",
         }
@@ -108,6 +112,27 @@ pub fn is_pacman_installed(name: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// Names of all installed pacman packages (one `pacman -Q` call).
+pub fn installed_pacman_packages() -> HashSet<String> {
+    installed_pacman_versions().into_keys().collect()
+}
+
+/// Installed pacman packages mapped to their versions.
+pub fn installed_pacman_versions() -> HashMap<String, String> {
+    std::process::Command::new("pacman")
+        .arg("-Q")
+        .stderr(std::process::Stdio::null())
+        .output()
+        .map(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Check if a Flatpak app is installed.
 pub fn is_flatpak_installed(app_id: &str) -> bool {
     std::process::Command::new("flatpak")
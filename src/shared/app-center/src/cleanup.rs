@@ -0,0 +1,540 @@
+use crate::installer::{run_install_cmd, InstallResult};
+use crate::pkgcache::{self, format_size};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// How many versions of each package to keep in the package caches,
+/// so a downgrade is still possible after cleaning.
+const KEEP_VERSIONS: usize = 2;
+
+/// What removing a cleanup item actually does.
+#[derive(Clone, Debug)]
+pub enum CleanupKind {
+    /// A pacman package nothing depends on (`pacman -Qdtq`).
+    Orphan(String),
+    /// An unused Flatpak runtime or extension, as `ID//BRANCH`.
+    FlatpakRuntime(String),
+    /// Files or directories to delete; `privileged` ones need pkexec.
+    Files { paths: Vec<PathBuf>, privileged: bool },
+}
+
+/// Something that can be removed to reclaim disk space.
+#[derive(Clone, Debug)]
+pub struct CleanupItem {
+    pub label: String,
+    pub detail: String,
+    pub size: u64,
+    pub kind: CleanupKind,
+}
+
+/// Collect everything that can be cleaned up. Categories whose tool is
+/// missing (e.g. no flatpak) are simply empty.
+pub fn scan() -> Vec<CleanupItem> {
+    let mut items = Vec::new();
+    items.extend(scan_orphans());
+    items.extend(scan_flatpak_unused());
+    items.extend(scan_pacman_cache());
+    items.extend(scan_paru_cache());
+    items.extend(scan_appimage_backups());
+    items
+}
+
+fn scan_orphans() -> Vec<CleanupItem> {
+    let Ok(out) = Command::new("pacman").arg("-Qdtq").stderr(Stdio::null()).output() else {
+        return Vec::new();
+    };
+    let names: Vec<String> = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if names.is_empty() {
+        return Vec::new();
+    }
+
+    let sizes = pacman_installed_sizes(&names);
+    names
+        .into_iter()
+        .map(|name| CleanupItem {
+            size: sizes.get(&name).copied().unwrap_or(0),
+            detail: "Orphaned dependency".into(),
+            label: name.clone(),
+            kind: CleanupKind::Orphan(name),
+        })
+        .collect()
+}
+
+/// Installed size of each package from `pacman -Qi`.
+fn pacman_installed_sizes(names: &[String]) -> HashMap<String, u64> {
+    let mut sizes = HashMap::new();
+    let Ok(out) = Command::new("pacman")
+        .arg("-Qi")
+        .args(names)
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return sizes;
+    };
+
+    let mut current = String::new();
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        match key.trim() {
+            "Name" => current = value.trim().to_string(),
+            "Installed Size" => {
+                if let Some(bytes) = parse_size(value) {
+                    sizes.insert(current.clone(), bytes);
+                }
+            }
+            _ => {}
+        }
+    }
+    sizes
+}
+
+/// Parse sizes as printed by pacman ("1.50 MiB") or flatpak ("1.2 GB").
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim().replace('\u{a0}', " ");
+    let (num, unit) = s.split_once(' ').unwrap_or((s.as_str(), "B"));
+    let value: f64 = num.replace(',', ".").parse().ok()?;
+    let factor = match unit.trim() {
+        "B" | "bytes" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        _ => return None,
+    };
+    Some((value * factor) as u64)
+}
+
+/// Runtimes and extensions no installed app needs, worked out from the
+/// refs' metadata like `flatpak uninstall --unused` does, without running
+/// anything that could remove them.
+fn scan_flatpak_unused() -> Vec<CleanupItem> {
+    let apps = flatpak_refs("--app");
+    let runtimes = flatpak_refs("--runtime");
+    if runtimes.is_empty() {
+        return Vec::new();
+    }
+    let pinned = flatpak_pinned();
+
+    let sizes = flatpak_runtime_sizes();
+    unused_runtimes(&apps, &runtimes, flatpak_metadata)
+        .into_iter()
+        .filter(|r| !pinned.iter().any(|p| p == r))
+        .filter_map(|r| {
+            let (id, branch) = split_ref(&r)?;
+            let r#ref = format!("{}//{}", id, branch);
+            Some(CleanupItem {
+                size: sizes.get(&r#ref).copied().unwrap_or(0),
+                detail: format!("Unused Flatpak runtime ({})", branch),
+                label: id.to_string(),
+                kind: CleanupKind::FlatpakRuntime(r#ref),
+            })
+        })
+        .collect()
+}
+
+/// Installed refs (`ID/ARCH/BRANCH`) of one kind: `--app` or `--runtime`.
+fn flatpak_refs(kind: &str) -> Vec<String> {
+    let Ok(out) = Command::new("flatpak")
+        .args(["list", kind, "--columns=ref"])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Runtimes kept with `flatpak pin`; printed as `runtime/ID/ARCH/BRANCH`.
+fn flatpak_pinned() -> Vec<String> {
+    let Ok(out) = Command::new("flatpak").arg("pin").stderr(Stdio::null()).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("runtime/"))
+        .map(str::to_string)
+        .collect()
+}
+
+fn flatpak_metadata(r#ref: &str) -> String {
+    Command::new("flatpak")
+        .args(["info", "--show-metadata", r#ref])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+/// "org.gnome.Platform/x86_64/45" → ("org.gnome.Platform", "45").
+fn split_ref(r#ref: &str) -> Option<(&str, &str)> {
+    let mut parts = r#ref.split('/');
+    let id = parts.next()?;
+    let _arch = parts.next()?;
+    Some((id, parts.next()?))
+}
+
+/// Walk from the apps through the runtimes and extensions their metadata
+/// asks for (and what those ask for in turn); every runtime not reached is
+/// unused. `metadata` returns a ref's `flatpak info --show-metadata`.
+fn unused_runtimes(apps: &[String], runtimes: &[String], metadata: impl Fn(&str) -> String) -> Vec<String> {
+    let mut needed: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = apps.iter().map(String::as_str).collect();
+    while let Some(current) = queue.pop_front() {
+        let Some((_, branch)) = split_ref(current) else { continue };
+        let wanted = parse_metadata(&metadata(current), branch);
+        for runtime in runtimes {
+            let Some((id, rt_branch)) = split_ref(runtime) else { continue };
+            let is_wanted = wanted.runtime.as_deref() == Some(runtime.as_str())
+                || wanted.extensions.iter().any(|(name, versions)| {
+                    let matches_id = id == name
+                        || id.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with('.'));
+                    matches_id && versions.iter().any(|v| v == rt_branch)
+                });
+            if is_wanted && needed.insert(runtime) {
+                queue.push_back(runtime);
+            }
+        }
+    }
+    runtimes.iter().filter(|r| !needed.contains(r.as_str())).cloned().collect()
+}
+
+/// What one ref's metadata asks for.
+#[derive(Debug, Default)]
+struct Requirements {
+    /// `runtime=` of an app, as `ID/ARCH/BRANCH`.
+    runtime: Option<String>,
+    /// `[Extension NAME]` groups with their allowed branches. Refs named
+    /// NAME or NAME.something satisfy them.
+    extensions: Vec<(String, Vec<String>)>,
+}
+
+/// Parse keyfile metadata. Extensions without `version(s)=` follow the
+/// branch of the ref declaring them.
+fn parse_metadata(text: &str, branch: &str) -> Requirements {
+    let mut req = Requirements::default();
+    let mut group = "";
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name;
+            if let Some(ext) = group.strip_prefix("Extension ") {
+                req.extensions.push((ext.trim().to_string(), vec![branch.to_string()]));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        match (group, key.trim()) {
+            ("Application", "runtime") => req.runtime = Some(value.trim().to_string()),
+            (g, "version" | "versions") if g.starts_with("Extension ") => {
+                if let Some((_, versions)) = req.extensions.last_mut() {
+                    *versions = value
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    req
+}
+
+fn flatpak_runtime_sizes() -> HashMap<String, u64> {
+    let Ok(out) = Command::new("flatpak")
+        .args(["list", "--runtime", "--columns=application,branch,size"])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split('\t');
+            let id = cols.next()?.trim();
+            let branch = cols.next()?.trim();
+            let size = parse_size(cols.next()?)?;
+            Some((format!("{}//{}", id, branch), size))
+        })
+        .collect()
+}
+
+fn scan_pacman_cache() -> Option<CleanupItem> {
+    let cache = Path::new(pkgcache::PACMAN_CACHE);
+    let old = pkgcache::old_versions(pkgcache::scan_dir(cache), KEEP_VERSIONS);
+    if old.is_empty() {
+        return None;
+    }
+    let size = old.iter().map(|p| p.size).sum();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for pkg in &old {
+        let sig = PathBuf::from(format!("{}.sig", pkg.path.display()));
+        if sig.exists() {
+            paths.push(sig);
+        }
+        paths.push(pkg.path.clone());
+    }
+    Some(CleanupItem {
+        label: "pacman package cache".into(),
+        detail: format!(
+            "{} old package files (cache total {}, keeps {} versions each)",
+            old.len(),
+            format_size(pkgcache::disk_usage(cache)),
+            KEEP_VERSIONS
+        ),
+        size,
+        kind: CleanupKind::Files {
+            paths,
+            privileged: true,
+        },
+    })
+}
+
+/// Old built packages plus clones of AUR packages that are no longer installed.
+fn scan_paru_cache() -> Option<CleanupItem> {
+    let clone_dir = pkgcache::paru_cache();
+    let installed = installed_names_and_bases();
+
+    // Clones are named by pkgbase; split packages and AUR packages named
+    // differently from their base only match through it or the .SRCINFO.
+    let stale_clones: Vec<PathBuf> = std::fs::read_dir(&clone_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .filter(|e| !installed.contains(&*e.file_name().to_string_lossy()))
+                .filter(|e| {
+                    let srcinfo = std::fs::read_to_string(e.path().join(".SRCINFO")).unwrap_or_default();
+                    !pkgcache::srcinfo_pkgnames(&srcinfo).iter().any(|name| installed.contains(*name))
+                })
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Old builds inside a stale clone go away with the clone itself.
+    let mut paths: Vec<PathBuf> = pkgcache::old_versions(pkgcache::scan_paru(), KEEP_VERSIONS)
+        .into_iter()
+        .map(|p| p.path)
+        .filter(|p| !stale_clones.iter().any(|d| p.starts_with(d)))
+        .collect();
+    paths.extend(stale_clones);
+    let size = paths.iter().map(|p| pkgcache::disk_usage(p)).sum();
+
+    if paths.is_empty() {
+        return None;
+    }
+    Some(CleanupItem {
+        label: "paru build cache".into(),
+        detail: format!(
+            "{} old builds and uninstalled clones (cache total {})",
+            paths.len(),
+            format_size(pkgcache::disk_usage(&clone_dir))
+        ),
+        size,
+        kind: CleanupKind::Files {
+            paths,
+            privileged: false,
+        },
+    })
+}
+
+/// Names and pkgbases of all installed pacman packages.
+fn installed_names_and_bases() -> HashSet<String> {
+    let Ok(out) = Command::new("pacman").arg("-Qi").stderr(Stdio::null()).output() else {
+        return HashSet::new();
+    };
+    parse_names_and_bases(&String::from_utf8_lossy(&out.stdout))
+}
+
+/// The "Name" and "Base" fields of `pacman -Qi` output.
+fn parse_names_and_bases(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| matches!(key.trim(), "Name" | "Base"))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty() && value != "None")
+        .collect()
+}
+
+/// `.bak` files left next to AppImages after an update.
+fn scan_appimage_backups() -> Vec<CleanupItem> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let dirs = [
+        (PathBuf::from("/opt/appimages"), true),
+        (PathBuf::from(&home).join(".local").join("bin"), false),
+    ];
+
+    let mut items = Vec::new();
+    for (dir, privileged) in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".bak") {
+                continue;
+            }
+            items.push(CleanupItem {
+                label: name,
+                detail: format!("AppImage backup in {}", dir.display()),
+                size: pkgcache::disk_usage(&entry.path()),
+                kind: CleanupKind::Files {
+                    paths: vec![entry.path()],
+                    privileged,
+                },
+            });
+        }
+    }
+    items
+}
+
+/// Remove the given items. Orphans and root-owned files go through pkexec
+/// in one call each so the user is asked for their password at most twice.
+pub fn remove(items: &[CleanupItem]) -> InstallResult {
+    let mut orphans = Vec::new();
+    let mut runtimes = Vec::new();
+    let mut root_files = Vec::new();
+    let mut user_files = Vec::new();
+    for item in items {
+        match &item.kind {
+            CleanupKind::Orphan(name) => orphans.push(name.as_str()),
+            CleanupKind::FlatpakRuntime(r) => runtimes.push(r.as_str()),
+            CleanupKind::Files { paths, privileged: true } => root_files.extend(paths),
+            CleanupKind::Files { paths, privileged: false } => user_files.extend(paths),
+        }
+    }
+
+    let mut errors = Vec::new();
+    if !orphans.is_empty() {
+        let mut args = vec!["pacman", "-Rns", "--noconfirm"];
+        args.extend(orphans);
+        let result = run_install_cmd("pkexec", &args, "Removing orphans...");
+        if !result.success {
+            errors.push(result.message);
+        }
+    }
+    if !runtimes.is_empty() {
+        let mut args = vec!["uninstall", "-y", "--noninteractive"];
+        args.extend(runtimes);
+        let result = run_install_cmd("flatpak", &args, "Removing runtimes...");
+        if !result.success {
+            errors.push(result.message);
+        }
+    }
+    if !root_files.is_empty() {
+        let mut args = vec!["rm", "-rf", "--"];
+        args.extend(root_files.iter().filter_map(|p| p.to_str()));
+        let result = run_install_cmd("pkexec", &args, "Removing files...");
+        if !result.success {
+            errors.push(result.message);
+        }
+    }
+    for path in user_files {
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        if let Err(e) = removed {
+            errors.push(format!("Failed: {}: {}", path.display(), e));
+        }
+    }
+
+    if errors.is_empty() {
+        let total = items.iter().map(|i| i.size).sum();
+        InstallResult {
+            success: true,
+            message: format!("Reclaimed {}", format_size(total)),
+        }
+    } else {
+        InstallResult {
+            success: false,
+            message: errors.join("; "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIMP: &str = "[Application]\nname=org.gimp.GIMP\nruntime=org.gnome.Platform/x86_64/45\n\
+        sdk=org.gnome.Sdk/x86_64/45\n\n[Extension org.gimp.GIMP.Plugin]\ndirectory=extensions\n\
+        version=2-40\nsubdirectories=true\n";
+    const GNOME: &str = "[Runtime]\nname=org.gnome.Platform\n\n[Extension org.gnome.Platform.Locale]\n\
+        directory=share/runtime/locale\n\n[Extension org.freedesktop.Platform.GL]\n\
+        versions=23.08;23.08-extra\nsubdirectories=true\n";
+
+    fn refs(list: &[&str]) -> Vec<String> {
+        list.iter().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn runtimes_reached_from_apps_are_used() {
+        let apps = refs(&["org.gimp.GIMP/x86_64/stable"]);
+        let runtimes = refs(&[
+            "org.gnome.Platform/x86_64/45",
+            "org.gnome.Platform.Locale/x86_64/45",
+            "org.freedesktop.Platform.GL.default/x86_64/23.08",
+            "org.gimp.GIMP.Plugin.GMic/x86_64/2-40",
+            "org.gnome.Platform/x86_64/44",
+            "org.freedesktop.Platform.GL.default/x86_64/22.08",
+            "org.kde.Platform/x86_64/5.15-23.08",
+        ]);
+        let metadata = |r: &str| match r {
+            "org.gimp.GIMP/x86_64/stable" => GIMP.to_string(),
+            "org.gnome.Platform/x86_64/45" => GNOME.to_string(),
+            _ => String::new(),
+        };
+        assert_eq!(
+            unused_runtimes(&apps, &runtimes, metadata),
+            [
+                "org.gnome.Platform/x86_64/44",
+                "org.freedesktop.Platform.GL.default/x86_64/22.08",
+                "org.kde.Platform/x86_64/5.15-23.08",
+            ]
+        );
+    }
+
+    #[test]
+    fn extension_names_match_whole_components() {
+        let req = parse_metadata(GNOME, "45");
+        assert_eq!(req.extensions[0], ("org.gnome.Platform.Locale".to_string(), vec!["45".to_string()]));
+        let unused = unused_runtimes(
+            &refs(&["app/x86_64/stable"]),
+            &refs(&["org.gnome.PlatformX/x86_64/stable"]),
+            |_| "[Extension org.gnome.Platform]\n".to_string(),
+        );
+        assert_eq!(unused, ["org.gnome.PlatformX/x86_64/stable"]);
+    }
+
+    #[test]
+    fn pacman_names_and_bases() {
+        let output = "Name            : python-pillow\nVersion         : 10.2.0-1\n\
+            Base            : python-pillow\n\nName            : lib32-mesa\nBase            : lib32-mesa\n\n\
+            Name            : zoom-bin\nBase            : zoom\n";
+        let found = parse_names_and_bases(output);
+        assert!(found.contains("zoom") && found.contains("zoom-bin") && found.contains("lib32-mesa"));
+        assert!(!found.contains("10.2.0-1"));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("1.50 MiB"), Some(1_572_864));
+        assert_eq!(parse_size("1,2\u{a0}GB"), Some(1_200_000_000));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("3 parsecs"), None);
+    }
+}
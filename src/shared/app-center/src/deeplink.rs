@@ -0,0 +1,126 @@
+use crate::catalog::AppEntry;
+use crate::sources;
+
+/// An app page requested on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeepLink {
+    /// `flatpak:org.gimp.GIMP`
+    Flatpak(String),
+    /// `aur:neovim-git`
+    Aur(String),
+    /// `appstream://org.gimp.GIMP` (or `appstream:org.gimp.GIMP`)
+    AppStream(String),
+    /// `appimage:Krita`
+    AppImage(String),
+}
+
+impl DeepLink {
+    /// Parse one command-line argument. Anything that isn't a link
+    /// (flags, plain words) returns `None`.
+    pub fn parse(arg: &str) -> Option<DeepLink> {
+        let (scheme, rest) = arg.split_once(':')?;
+        let id = rest.trim_start_matches("//").trim_end_matches('/');
+        if id.is_empty() {
+            return None;
+        }
+        match scheme {
+            "flatpak" => Some(DeepLink::Flatpak(id.to_string())),
+            "aur" => Some(DeepLink::Aur(id.to_string())),
+            "appimage" => Some(DeepLink::AppImage(id.to_string())),
+            "appstream" => {
+                // Component ids sometimes carry the desktop file suffix.
+                let id = id.strip_suffix(".desktop").unwrap_or(id);
+                Some(DeepLink::AppStream(id.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// First deep link among the program arguments.
+    pub fn from_args(args: &[String]) -> Option<DeepLink> {
+        args.iter().skip(1).find_map(|a| DeepLink::parse(a))
+    }
+
+    /// The id shown in status messages.
+    pub fn id(&self) -> &str {
+        match self {
+            DeepLink::Flatpak(id)
+            | DeepLink::Aur(id)
+            | DeepLink::AppStream(id)
+            | DeepLink::AppImage(id) => id,
+        }
+    }
+
+    /// Fetch the app's entry. AppStream ids are looked up on Flathub first,
+    /// then as an AUR package name.
+    pub fn resolve(&self) -> Option<AppEntry> {
+        match self {
+            DeepLink::Flatpak(id) => sources::flathub::get_details(id),
+            DeepLink::Aur(name) => sources::aur::info(name),
+            DeepLink::AppStream(id) => sources::flathub::get_details(id)
+                .or_else(|| sources::aur::info(&id.to_lowercase())),
+            DeepLink::AppImage(name) => sources::appimage::info(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_schemes() {
+        assert_eq!(DeepLink::parse("flatpak:org.gimp.GIMP"), Some(DeepLink::Flatpak("org.gimp.GIMP".into())));
+        assert_eq!(DeepLink::parse("aur:neovim-git"), Some(DeepLink::Aur("neovim-git".into())));
+        assert_eq!(DeepLink::parse("appimage:Krita"), Some(DeepLink::AppImage("Krita".into())));
+    }
+
+    #[test]
+    fn appstream_forms() {
+        let gimp = Some(DeepLink::AppStream("org.gimp.GIMP".into()));
+        assert_eq!(DeepLink::parse("appstream://org.gimp.GIMP"), gimp);
+        assert_eq!(DeepLink::parse("appstream:org.gimp.GIMP"), gimp);
+        // What browsers and GNOME Software hand over.
+        assert_eq!(DeepLink::parse("appstream://org.gimp.GIMP/"), gimp);
+        assert_eq!(DeepLink::parse("appstream://org.gimp.GIMP.desktop"), gimp);
+    }
+
+    #[test]
+    fn malformed_links() {
+        for arg in [
+            "",
+            ":",
+            "flatpak:",
+            "aur:",
+            "appstream://",
+            "appstream:///",
+            "appimage:",
+            "gimp",
+            "--verbose",
+            "https://flathub.org/apps/org.gimp.GIMP",
+            "FLATPAK:org.gimp.GIMP",
+        ] {
+            assert_eq!(DeepLink::parse(arg), None, "{arg:?}");
+        }
+    }
+
+    /// xdg-open hands `appstream://` links to app-center, with the URL
+    /// as the argument.
+    #[test]
+    fn appstream_links_are_routed_here() {
+        let mimeapps = include_str!("../../configs/mimeapps.list");
+        assert!(mimeapps.lines().any(|l| l == "x-scheme-handler/appstream=app-center.desktop"));
+        let desktop = include_str!("../app-center.desktop");
+        assert!(desktop.lines().any(|l| l.starts_with("MimeType=") && l.contains("x-scheme-handler/appstream;")));
+        let exec = desktop.lines().find_map(|l| l.strip_prefix("Exec=")).unwrap();
+        assert!(exec.ends_with(" %u"));
+    }
+
+    #[test]
+    fn first_link_after_the_program_name() {
+        let args = ["aur:app-center", "--debug", "flatpak:org.gimp.GIMP", "aur:neovim-git"].map(String::from);
+        assert_eq!(DeepLink::from_args(&args), Some(DeepLink::Flatpak("org.gimp.GIMP".into())));
+        assert_eq!(DeepLink::from_args(&args[..2]), None);
+        assert_eq!(DeepLink::from_args(&[]), None);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::catalog::{cache_dir, Source};
+use crate::installer::{run_install_cmd, InstallResult};
+use crate::pkgcache::{self, CachedPackage};
+use crate::vercmp::vercmp;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Drop-in holding the `IgnorePkg` lines managed by app-center.
+const IGNORE_DROPIN: &str = "/etc/pacman.d/smplos-ignorepkg.conf";
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// Where an installable older version comes from.
+#[derive(Clone, Debug)]
+pub enum VersionTarget {
+    /// A package file in the pacman or paru cache.
+    Package(PathBuf),
+    /// A Flatpak commit on the remote.
+    Commit(String),
+}
+
+/// One version that can be installed.
+#[derive(Clone, Debug)]
+pub struct Version {
+    pub label: String,
+    pub detail: String,
+    pub current: bool,
+    pub target: VersionTarget,
+}
+
+/// List the versions available for an installed app, newest first.
+pub fn list_versions(source: &Source, id: &str) -> Vec<Version> {
+    match source {
+        Source::Aur | Source::Repo => package_versions(id),
+        Source::Flatpak => flatpak_commits(id),
+        Source::AppImage => Vec::new(),
+    }
+}
+
+/// Install one of the versions returned by `list_versions`.
+pub fn install_version(id: &str, target: &VersionTarget) -> InstallResult {
+    let result = match target {
+        VersionTarget::Package(path) => {
+            let path = path.to_string_lossy();
+            run_install_cmd("pkexec", &["pacman", "-U", "--noconfirm", &path], "")
+        }
+        VersionTarget::Commit(commit) => run_install_cmd(
+            "flatpak",
+            &["update", "-y", "--noninteractive", "--user", &format!("--commit={}", commit), id],
+            "",
+        ),
+    };
+    if result.success {
+        InstallResult {
+            success: true,
+            message: format!("Switched {} to the selected version", id),
+        }
+    } else {
+        result
+    }
+}
+
+// -- pacman / paru --
+
+fn package_versions(name: &str) -> Vec<Version> {
+    let info = pacman_info(name);
+    let installed = qi_field(&info, "Version");
+    let clone = pkgcache::paru_clone(name, qi_field(&info, "Base"));
+    let packages: Vec<_> = pkgcache::scan_dir(Path::new(pkgcache::PACMAN_CACHE))
+        .into_iter()
+        .chain(clone.map(|dir| pkgcache::scan_dir(&dir)).unwrap_or_default())
+        .filter(|p| p.name == name)
+        .collect();
+
+    newest_first(packages)
+        .into_iter()
+        .map(|p| Version {
+            current: installed == Some(p.version.as_str()),
+            detail: format!(
+                "{} ({})",
+                p.path.parent().map(|d| d.display().to_string()).unwrap_or_default(),
+                pkgcache::format_size(p.size)
+            ),
+            label: p.version,
+            target: VersionTarget::Package(p.path),
+        })
+        .collect()
+}
+
+/// Cached packages sorted by version, newest first. The same build can
+/// sit in both caches; the most recently written copy is kept.
+fn newest_first(mut packages: Vec<CachedPackage>) -> Vec<CachedPackage> {
+    packages.sort_by(|a, b| vercmp(&b.version, &a.version).then(b.modified.cmp(&a.modified)));
+    let mut seen = HashSet::new();
+    packages.retain(|p| seen.insert(p.version.clone()));
+    packages
+}
+
+/// `pacman -Qi` output for an installed package; empty if it isn't.
+fn pacman_info(name: &str) -> String {
+    Command::new("pacman")
+        .args(["-Qi", name])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+        .unwrap_or_default()
+}
+
+/// A field of `pacman -Qi` output ("Version", "Base"); pacman prints
+/// "None" for missing ones.
+fn qi_field<'a>(info: &'a str, key: &str) -> Option<&'a str> {
+    info.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim())
+        .filter(|v| !v.is_empty() && *v != "None")
+}
+
+/// Packages currently listed in the managed `IgnorePkg` drop-in.
+fn ignored_packages() -> Vec<String> {
+    parse_ignore_pkg(&std::fs::read_to_string(IGNORE_DROPIN).unwrap_or_default())
+}
+
+/// Package names from the `IgnorePkg = ...` lines of a pacman config.
+fn parse_ignore_pkg(conf: &str) -> Vec<String> {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("IgnorePkg"))
+        .filter_map(|rest| rest.trim_start().strip_prefix('='))
+        .flat_map(|names| names.split_whitespace().map(String::from).collect::<Vec<_>>())
+        .collect()
+}
+
+// -- Flatpak --
+
+fn flatpak_commits(app_id: &str) -> Vec<Version> {
+    let current = flatpak_current_commit(app_id);
+    let Some(origin) = flatpak_origin(app_id) else {
+        return Vec::new();
+    };
+    let Ok(out) = Command::new("flatpak")
+        .args(["remote-info", "--user", "--log", &origin, app_id])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    parse_remote_log(&String::from_utf8_lossy(&out.stdout))
+        .into_iter()
+        .map(|(commit, subject, date)| Version {
+            label: format!("{} {}", &commit[..commit.len().min(12)], date),
+            detail: subject,
+            current: current.as_deref() == Some(commit.as_str()),
+            target: VersionTarget::Commit(commit),
+        })
+        .collect()
+}
+
+/// Extract (commit, subject, date) triples from `flatpak remote-info --log`.
+fn parse_remote_log(output: &str) -> Vec<(String, String, String)> {
+    let mut entries = Vec::new();
+    let mut commit: Option<String> = None;
+    let mut subject = String::new();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "Commit" => {
+                commit = Some(value);
+                subject.clear();
+            }
+            "Subject" => subject = value,
+            "Date" => {
+                if let Some(c) = commit.take() {
+                    entries.push((c, subject.clone(), value));
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// Remote the app was installed from (`flatpak info --show-origin`).
+fn flatpak_origin(app_id: &str) -> Option<String> {
+    let out = Command::new("flatpak")
+        .args(["info", "--user", "--show-origin", app_id])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let origin = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !origin.is_empty()).then_some(origin)
+}
+
+fn flatpak_current_commit(app_id: &str) -> Option<String> {
+    let out = Command::new("flatpak")
+        .args(["info", "--user", app_id])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim() == "Commit")
+        .map(|(_, v)| v.trim().to_string())
+}
+
+fn flatpak_masks() -> Vec<String> {
+    Command::new("flatpak")
+        .args(["mask", "--user"])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// -- Pinning --
+
+/// Whether updates for this app are currently held back.
+pub fn is_pinned(source: &Source, id: &str) -> bool {
+    match source {
+        Source::Aur | Source::Repo => ignored_packages().iter().any(|p| p == id),
+        Source::Flatpak => flatpak_masks().iter().any(|p| p == id),
+        Source::AppImage => false,
+    }
+}
+
+/// Hold back (or release) updates: `IgnorePkg` for packages, `flatpak mask`
+/// for Flatpaks.
+pub fn set_pinned(source: &Source, id: &str, pin: bool) -> InstallResult {
+    let result = match source {
+        Source::Aur | Source::Repo => set_ignore_pkg(id, pin),
+        Source::Flatpak => {
+            let mut args = vec!["mask", "--user"];
+            if !pin {
+                args.push("--remove");
+            }
+            args.push(id);
+            run_install_cmd("flatpak", &args, "")
+        }
+        Source::AppImage => InstallResult {
+            success: false,
+            message: "AppImages are never updated automatically".into(),
+        },
+    };
+    if !result.success {
+        return result;
+    }
+    InstallResult {
+        success: true,
+        message: if pin {
+            format!("{} will be skipped by updates", id)
+        } else {
+            format!("{} will be updated again", id)
+        },
+    }
+}
+
+/// Rewrite the managed drop-in and make sure pacman.conf includes it.
+/// Both files are staged in the cache dir and installed with one pkexec call.
+fn set_ignore_pkg(name: &str, pin: bool) -> InstallResult {
+    let mut ignored = ignored_packages();
+    ignored.retain(|p| p != name);
+    if pin {
+        ignored.push(name.to_string());
+    }
+
+    let mut dropin = String::from("# Managed by app-center -- packages held at their current version.\n");
+    if !ignored.is_empty() {
+        dropin.push_str(&format!("IgnorePkg = {}\n", ignored.join(" ")));
+    }
+    let staged_dropin = cache_dir().join("smplos-ignorepkg.conf");
+    if let Err(e) = std::fs::write(&staged_dropin, dropin) {
+        return InstallResult {
+            success: false,
+            message: format!("Failed: {}", e),
+        };
+    }
+
+    let pacman_conf = std::fs::read_to_string(PACMAN_CONF).unwrap_or_default();
+    let staged_conf = cache_dir().join("pacman.conf");
+    let conf_arg = match with_dropin_include(&pacman_conf) {
+        Some(updated) => {
+            if let Err(e) = std::fs::write(&staged_conf, updated) {
+                return InstallResult {
+                    success: false,
+                    message: format!("Failed: {}", e),
+                };
+            }
+            staged_conf.to_string_lossy().to_string()
+        }
+        None => String::new(),
+    };
+
+    let staged_dropin = staged_dropin.to_string_lossy();
+    run_install_cmd(
+        "pkexec",
+        &[
+            "sh",
+            "-c",
+            r#"install -Dm644 "$1" "$2" && { [ -z "$3" ] || install -m644 "$3" "$4"; }"#,
+            "sh",
+            &staged_dropin,
+            IGNORE_DROPIN,
+            &conf_arg,
+            PACMAN_CONF,
+        ],
+        "",
+    )
+}
+
+/// pacman.conf with an `Include` of the drop-in added to `[options]`,
+/// or `None` if it is already there.
+fn with_dropin_include(conf: &str) -> Option<String> {
+    let include = format!("Include = {}", IGNORE_DROPIN);
+    if conf.lines().any(|l| l.trim() == include) {
+        return None;
+    }
+    let mut out = String::with_capacity(conf.len() + include.len() + 1);
+    let mut added = false;
+    for line in conf.lines() {
+        out.push_str(line);
+        out.push('\n');
+        if !added && line.trim() == "[options]" {
+            out.push_str(&include);
+            out.push('\n');
+            added = true;
+        }
+    }
+    if !added {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    const REMOTE_LOG: &str = "        ID: org.gimp.GIMP
+       Ref: app/org.gimp.GIMP/x86_64/stable
+      Arch: x86_64
+    Branch: stable
+   Version: 2.10.38
+Collection: org.flathub.Stable
+  Download: 131.9 MB
+ Installed: 412.4 MB
+   Runtime: org.gnome.Platform/x86_64/46
+
+    Commit: 8c3e0c6a1d5e4b0f2a9d7c1e3b5a7f9e0d2c4b6a8e0f1a3c5e7b9d1f3a5c7e9b
+    Parent: 0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0
+   Subject: Update to 2.10.38 (4f1c2a9)
+      Date: 2024-05-03 10:12:45 +0000
+
+   History:
+
+    Commit: 0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0
+   Subject: Rebuild against GNOME 46: fixes file chooser (9e8d7c6)
+      Date: 2024-04-11 08:01:02 +0000
+
+    Commit: 1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809
+      Date: 2024-02-20 17:45:00 +0000
+";
+
+    #[test]
+    fn remote_log_entries() {
+        let entries = parse_remote_log(REMOTE_LOG);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            (
+                "8c3e0c6a1d5e4b0f2a9d7c1e3b5a7f9e0d2c4b6a8e0f1a3c5e7b9d1f3a5c7e9b".into(),
+                "Update to 2.10.38 (4f1c2a9)".into(),
+                "2024-05-03 10:12:45 +0000".into()
+            )
+        );
+        // Colons in the subject stay in it.
+        assert_eq!(entries[1].1, "Rebuild against GNOME 46: fixes file chooser (9e8d7c6)");
+        // A commit without a subject doesn't inherit the previous one.
+        assert_eq!(entries[2].1, "");
+    }
+
+    #[test]
+    fn include_goes_after_options() {
+        let conf = "#\n# /etc/pacman.conf\n#\n[options]\nHoldPkg = pacman glibc\nArchitecture = auto\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+        let updated = with_dropin_include(conf).unwrap();
+        assert_eq!(
+            updated,
+            "#\n# /etc/pacman.conf\n#\n[options]\nInclude = /etc/pacman.d/smplos-ignorepkg.conf\n\
+             HoldPkg = pacman glibc\nArchitecture = auto\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n"
+        );
+        // Already included: nothing to write.
+        assert_eq!(with_dropin_include(&updated), None);
+    }
+
+    #[test]
+    fn include_needs_an_options_section() {
+        assert_eq!(with_dropin_include("[core]\nInclude = /etc/pacman.d/mirrorlist\n"), None);
+        assert_eq!(with_dropin_include(""), None);
+    }
+
+    #[test]
+    fn ignore_pkg_lines() {
+        let dropin = "# Managed by app-center\nIgnorePkg = firefox  linux\nIgnorePkg=gimp\n#IgnorePkg = vim\nIgnoreGroup = gnome\n";
+        assert_eq!(parse_ignore_pkg(dropin), ["firefox", "linux", "gimp"]);
+        assert!(parse_ignore_pkg("").is_empty());
+    }
+
+    fn cached(dir: &str, version: &str, age_secs: u64) -> CachedPackage {
+        CachedPackage {
+            path: PathBuf::from(dir).join(format!("foo-{version}-x86_64.pkg.tar.zst")),
+            name: "foo".into(),
+            version: version.into(),
+            size: 1,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 - age_secs),
+        }
+    }
+
+    #[test]
+    fn versions_sort_by_vercmp_and_dedup() {
+        let packages = vec![
+            cached("/var/cache/pacman/pkg", "1.9-1", 10),
+            cached("/var/cache/pacman/pkg", "1.10-1", 500),
+            cached("/home/u/.cache/paru/clone/foo", "1.10-1", 20),
+            cached("/var/cache/pacman/pkg", "1:0.5-1", 900),
+            cached("/var/cache/pacman/pkg", "1.10-2", 30),
+        ];
+        let sorted = newest_first(packages);
+        let versions: Vec<&str> = sorted.iter().map(|p| p.version.as_str()).collect();
+        assert_eq!(versions, ["1:0.5-1", "1.10-2", "1.10-1", "1.9-1"]);
+        // Of the two 1.10-1 copies, the more recent file is kept.
+        assert!(sorted[2].path.starts_with("/home/u/.cache/paru"));
+    }
+
+    #[test]
+    fn pacman_info_fields() {
+        let info = "Name            : linux-zen-git-headers\nVersion         : 6.8.r1.g0a1b2c-1\n\
+                    Base            : linux-zen-git\nDescription     : Headers: for building modules\n\
+                    Replaces        : None\n";
+        assert_eq!(qi_field(info, "Version"), Some("6.8.r1.g0a1b2c-1"));
+        assert_eq!(qi_field(info, "Base"), Some("linux-zen-git"));
+        assert_eq!(qi_field(info, "Replaces"), None);
+        assert_eq!(qi_field("", "Version"), None);
+    }
+}
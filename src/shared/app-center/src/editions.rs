@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+/// A smplOS edition offered as an installable bundle.
+#[derive(Clone, Debug)]
+pub struct Edition {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Official-repo packages (`packages-extra.txt`).
+    pub repo: Vec<String>,
+    /// AUR packages (`packages-aur-extra.txt`).
+    pub aur: Vec<String>,
+}
+
+/// One package of an edition, with its install state.
+#[derive(Clone, Debug)]
+pub struct BundlePackage {
+    pub name: String,
+    pub aur: bool,
+    pub installed: bool,
+}
+
+/// (id, description.txt, packages-extra.txt, packages-aur-extra.txt),
+/// generated by build.rs from `src/editions/` so app-center always matches
+/// the ISO it was built with.
+const EDITION_LISTS: &[(&str, &str, &str, &str)] = include!(concat!(env!("OUT_DIR"), "/editions.rs"));
+
+/// Non-comment lines of an edition file: `#` starts a comment.
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// Parse a package list: one name per line.
+pub fn parse_package_list(text: &str) -> Vec<String> {
+    entries(text).map(String::from).collect()
+}
+
+/// Display name and description from `description.txt`: the name on the
+/// first line, the description on the second. Falls back to the id.
+fn edition_info<'a>(id: &'a str, text: &'a str) -> (&'a str, &'a str) {
+    let mut lines = entries(text);
+    (lines.next().unwrap_or(id), lines.next().unwrap_or(""))
+}
+
+/// All editions that add at least one package, sorted by id. Editions
+/// with empty package lists (like Lite) have nothing to install and are
+/// left out.
+pub fn load() -> Vec<Edition> {
+    EDITION_LISTS
+        .iter()
+        .map(|&(id, info, repo, aur)| {
+            let (name, description) = edition_info(id, info);
+            Edition {
+                name,
+                description,
+                repo: parse_package_list(repo),
+                aur: parse_package_list(aur),
+            }
+        })
+        .filter(|e| !e.repo.is_empty() || !e.aur.is_empty())
+        .collect()
+}
+
+impl Edition {
+    /// Packages of this edition, repo packages first, with install state
+    /// looked up in `installed` (see `catalog::installed_pacman_packages`).
+    pub fn packages(&self, installed: &HashSet<String>) -> Vec<BundlePackage> {
+        let repo = self.repo.iter().map(|p| (p, false));
+        let aur = self.aur.iter().map(|p| (p, true));
+        repo.chain(aur)
+            .map(|(name, aur)| BundlePackage {
+                name: name.clone(),
+                aur,
+                installed: installed.contains(name),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_lists_skip_comments() {
+        let text = "# smplOS Creators Edition\nobs-studio\n\ngimp  # image editing\n";
+        assert_eq!(parse_package_list(text), ["obs-studio", "gimp"]);
+    }
+
+    #[test]
+    fn info_comes_from_description_file() {
+        let text = "# Shown in app-center\nAI\nAI tools: local model runners.\n";
+        assert_eq!(edition_info("ai", text), ("AI", "AI tools: local model runners."));
+        assert_eq!(edition_info("lite", ""), ("lite", ""));
+    }
+
+    #[test]
+    fn every_bundle_has_a_description() {
+        let editions = load();
+        assert!(!editions.is_empty());
+        for edition in editions {
+            assert!(!edition.description.is_empty(), "{} has no description.txt", edition.name);
+        }
+    }
+}
@@ -11,6 +11,7 @@ pub struct InstallResult {
 pub fn install(source: &Source, id: &str) -> InstallResult {
     match source {
         Source::Aur => install_aur(id),
+        Source::Repo => install_repo_packages(&[id.to_string()]),
         Source::Flatpak => install_flatpak(id),
         Source::AppImage => install_appimage(id),
     }
@@ -19,12 +20,52 @@ pub fn install(source: &Source, id: &str) -> InstallResult {
 /// Uninstall a package from the given source.
 pub fn uninstall(source: &Source, id: &str, name: &str) -> InstallResult {
     match source {
-        Source::Aur => uninstall_aur(id),
+        Source::Aur | Source::Repo => uninstall_aur(id),
         Source::Flatpak => uninstall_flatpak(id),
         Source::AppImage => uninstall_appimage(name),
     }
 }
 
+/// Install several official-repo packages in one pacman transaction.
+/// Already-installed packages are skipped (`--needed`).
+pub fn install_repo_packages(names: &[String]) -> InstallResult {
+    let mut args = vec!["pacman", "-S", "--needed", "--noconfirm"];
+    args.extend(names.iter().map(String::as_str));
+    run_install_cmd("pkexec", &args, "Installing packages...")
+}
+
+/// Install several AUR packages in one paru run.
+pub fn install_aur_packages(names: &[String]) -> InstallResult {
+    if !which_exists("paru") {
+        return InstallResult {
+            success: false,
+            message: "paru is not installed. Run: sudo pacman -S paru".into(),
+        };
+    }
+    let mut args = vec!["-S", "--needed", "--noconfirm"];
+    args.extend(names.iter().map(String::as_str));
+    run_install_cmd("paru", &args, "Installing AUR packages...")
+}
+
+/// Full system upgrade. Arch does not support upgrading single packages
+/// (partial upgrades), so security fixes always go through `-Syu`.
+pub fn upgrade_system() -> InstallResult {
+    let (cmd, args) = if which_exists("paru") {
+        ("paru", vec!["-Syu", "--noconfirm"])
+    } else {
+        ("pkexec", vec!["pacman", "-Syu", "--noconfirm"])
+    };
+    let result = run_install_cmd(cmd, &args, "Upgrading system...");
+    if result.success {
+        InstallResult {
+            success: true,
+            message: "System upgraded".into(),
+        }
+    } else {
+        result
+    }
+}
+
 fn install_aur(name: &str) -> InstallResult {
     // Use paru if available (handles AUR), else fall back to pacman
     let (cmd, args) = if which_exists("paru") {
@@ -114,7 +155,7 @@ fn uninstall_appimage(name: &str) -> InstallResult {
     }
 }
 
-fn run_install_cmd(cmd: &str, args: &[&str], _msg: &str) -> InstallResult {
+pub fn run_install_cmd(cmd: &str, args: &[&str], _msg: &str) -> InstallResult {
     match Command::new(cmd).args(args).output() {
         Ok(output) => {
             if output.status.success() {
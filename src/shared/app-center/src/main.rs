@@ -1,9 +1,23 @@
+mod advisories;
 mod catalog;
+mod cleanup;
+mod deeplink;
+mod downgrade;
+mod editions;
 mod installer;
+mod pkgcache;
+mod queue;
 mod sources;
 mod theme;
+mod vercmp;
 
+use advisories::Vulnerability;
 use catalog::{merge_results, AppEntry, Source};
+use cleanup::{CleanupItem, CleanupKind};
+use downgrade::Version;
+use editions::Edition;
+use queue::{InstallQueue, Job, JobDone};
+use std::collections::HashSet;
 use i_slint_backend_winit::WinitWindowAccessor;
 use slint::{Model, ModelRc, SharedString, VecModel};
 use std::cell::RefCell;
@@ -11,7 +25,12 @@ use std::rc::Rc;
 
 slint::include_modules!();
 
-fn to_ui_item(app: &AppEntry) -> AppItem {
+fn to_ui_item(app: &AppEntry, vulns: &[Vulnerability]) -> AppItem {
+    let advisory = if matches!(app.source, Source::Aur | Source::Repo) && app.installed {
+        vulns.iter().find(|v| v.package == app.id)
+    } else {
+        None
+    };
     AppItem {
         name: app.name.clone().into(),
         id: app.id.clone().into(),
@@ -23,9 +42,131 @@ fn to_ui_item(app: &AppEntry) -> AppItem {
         votes: app.votes as i32,
         popularity: app.popularity as f32,
         installed: app.installed,
+        advisory: advisory.map(|v| v.severity.clone()).unwrap_or_default().into(),
+    }
+}
+
+fn to_security_item(vuln: &Vulnerability) -> SecurityItem {
+    SecurityItem {
+        package: vuln.package.clone().into(),
+        installed: vuln.installed.clone().into(),
+        fixed: vuln.fixed.clone().unwrap_or_else(|| "no fix yet".into()).into(),
+        severity: vuln.severity.clone().into(),
+        cves: vuln.cves.join(", ").into(),
+    }
+}
+
+/// Re-match the advisory feed against installed versions and refresh the
+/// security view, the header count and the result badges.
+fn update_security(
+    ui: &MainWindow,
+    groups: &[advisories::AdvisoryGroup],
+    vulns: &Rc<RefCell<Vec<Vulnerability>>>,
+    state: &Rc<RefCell<Vec<AppEntry>>>,
+    model: &Rc<VecModel<AppItem>>,
+) {
+    let matched = advisories::match_installed(groups, &catalog::installed_pacman_versions());
+    ui.set_security_items(ModelRc::new(VecModel::from(
+        matched.iter().map(to_security_item).collect::<Vec<_>>(),
+    )));
+    ui.set_security_count(matched.len() as i32);
+    *vulns.borrow_mut() = matched;
+    let vulns = vulns.borrow();
+    for (idx, app) in state.borrow().iter().enumerate() {
+        model.set_row_data(idx, to_ui_item(app, &vulns));
+    }
+}
+
+/// Row of `app` in the current results, if it's still listed.
+fn row_of(results: &[AppEntry], app: &AppEntry) -> Option<usize> {
+    results.iter().position(|a| a.id == app.id && a.source == app.source)
+}
+
+fn to_bundle_item(edition: &Edition, installed: &HashSet<String>) -> BundleItem {
+    let packages = edition.packages(installed);
+    BundleItem {
+        name: edition.name.into(),
+        description: edition.description.into(),
+        package_count: packages.len() as i32,
+        installed_count: packages.iter().filter(|p| p.installed).count() as i32,
     }
 }
 
+/// Fill the bundle checklist; packages that are missing start checked.
+fn show_bundle_packages(ui: &MainWindow, edition: &Edition, installed: &HashSet<String>) {
+    let items: Vec<BundlePackageItem> = edition
+        .packages(installed)
+        .into_iter()
+        .map(|p| BundlePackageItem {
+            name: p.name.into(),
+            source: SharedString::from(if p.aur { "AUR" } else { "Repo" }),
+            installed: p.installed,
+            checked: !p.installed,
+        })
+        .collect();
+    ui.set_bundle_packages(ModelRc::new(VecModel::from(items)));
+}
+
+fn to_cleanup_entry(item: &CleanupItem) -> CleanupEntry {
+    CleanupEntry {
+        label: item.label.clone().into(),
+        detail: item.detail.clone().into(),
+        size: pkgcache::format_size(item.size).into(),
+        // Orphans are opt-in: "unneeded" packages are sometimes used directly.
+        checked: !matches!(item.kind, CleanupKind::Orphan(_)),
+    }
+}
+
+/// Rescan cleanup candidates and show them.
+fn show_cleanup(ui: &MainWindow, items: &Rc<RefCell<Vec<CleanupItem>>>) {
+    let scanned = cleanup::scan();
+    let entries: Vec<CleanupEntry> = scanned.iter().map(to_cleanup_entry).collect();
+    *items.borrow_mut() = scanned;
+    ui.set_cleanup_items(ModelRc::new(VecModel::from(entries)));
+    update_cleanup_total(ui, &items.borrow());
+}
+
+/// Show the space reclaimed by the checked cleanup items.
+fn update_cleanup_total(ui: &MainWindow, items: &[CleanupItem]) {
+    let entries = ui.get_cleanup_items();
+    let total: u64 = items
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| entries.row_data(*i).is_some_and(|e| e.checked))
+        .map(|(_, item)| item.size)
+        .sum();
+    ui.set_cleanup_total(pkgcache::format_size(total).into());
+}
+
+/// Load the installable versions of an app into the versions panel.
+fn show_versions(ui: &MainWindow, app: &AppEntry, versions: &Rc<RefCell<Vec<Version>>>) {
+    let list = downgrade::list_versions(&app.source, &app.id);
+    let items: Vec<VersionItem> = list
+        .iter()
+        .map(|v| VersionItem {
+            label: v.label.clone().into(),
+            detail: v.detail.clone().into(),
+            current: v.current,
+        })
+        .collect();
+    *versions.borrow_mut() = list;
+    ui.set_versions(ModelRc::new(VecModel::from(items)));
+    ui.set_show_versions(true);
+}
+
+/// Add a job to the install queue and reflect it in the status line.
+fn enqueue(ui: &MainWindow, queue: &InstallQueue, job: Job) {
+    let label = job.label();
+    queue.push(job);
+    ui.set_installing(true);
+    let waiting = queue.pending() - 1;
+    ui.set_install_status(SharedString::from(if waiting > 0 {
+        format!("Queued: {} ({} ahead)", label, waiting)
+    } else {
+        label
+    }));
+}
+
 fn apply_theme(ui: &MainWindow) {
     let palette = theme::load_theme_from_eww_scss(&format!(
         "{}/.config/eww/theme-colors.scss",
@@ -54,8 +195,11 @@ fn do_search(query: &str, aur: bool, flatpak: bool, appimage: bool) -> Vec<AppEn
 
     let mut results = Vec::new();
 
+    // The AUR filter covers every pacman package; installed repo packages
+    // are listed so their security advisories show up too.
     if aur {
         results.extend(sources::aur::search(query));
+        results.extend(sources::pacman::search_installed(query));
     }
     if flatpak {
         results.extend(sources::flathub::search(query));
@@ -71,10 +215,12 @@ fn update_results(
     ui: &MainWindow,
     state: &Rc<RefCell<Vec<AppEntry>>>,
     model: &Rc<VecModel<AppItem>>,
+    vulns: &Rc<RefCell<Vec<Vulnerability>>>,
     results: Vec<AppEntry>,
 ) {
     *state.borrow_mut() = results.clone();
-    model.set_vec(results.iter().map(to_ui_item).collect::<Vec<_>>());
+    let vulns = vulns.borrow();
+    model.set_vec(results.iter().map(|app| to_ui_item(app, &vulns)).collect::<Vec<_>>());
     ui.set_results(ModelRc::from(model.clone()));
 
     let len = model.row_count() as i32;
@@ -95,7 +241,8 @@ fn update_results(
 }
 
 fn main() -> Result<(), slint::PlatformError> {
-    for arg in std::env::args() {
+    let args: Vec<String> = std::env::args().collect();
+    for arg in &args {
         if arg == "-v" || arg == "--version" {
             println!("app-center v{}", env!("CARGO_PKG_VERSION"));
             return Ok(());
@@ -116,23 +263,35 @@ fn main() -> Result<(), slint::PlatformError> {
     slint::platform::set_platform(Box::new(backend))
         .map_err(|e| slint::PlatformError::Other(e.to_string()))?;
 
+    // --offline (or SMPLOS_OFFLINE=1): search Flatpaks from local appstream data
+    let offline = args.iter().any(|a| a == "--offline")
+        || std::env::var("SMPLOS_OFFLINE").is_ok_and(|v| v == "1");
+    sources::flathub::set_offline(offline);
+
     let ui = MainWindow::new()?;
     apply_theme(&ui);
 
     let state: Rc<RefCell<Vec<AppEntry>>> = Rc::new(RefCell::new(Vec::new()));
     let model = Rc::new(VecModel::<AppItem>::default());
+    let bundles: Rc<Vec<Edition>> = Rc::new(editions::load());
+    let cleanup_items: Rc<RefCell<Vec<CleanupItem>>> = Rc::new(RefCell::new(Vec::new()));
+    let versions: Rc<RefCell<Vec<Version>>> = Rc::new(RefCell::new(Vec::new()));
+    let vulns: Rc<RefCell<Vec<Vulnerability>>> = Rc::new(RefCell::new(Vec::new()));
+    let advisory_groups: Rc<RefCell<Vec<advisories::AdvisoryGroup>>> =
+        Rc::new(RefCell::new(Vec::new()));
 
     // -- Search callback --
     {
         let ui_weak = ui.as_weak();
         let state = state.clone();
         let model = model.clone();
+        let vulns = vulns.clone();
         ui.on_search(move |query| {
             let Some(ui) = ui_weak.upgrade() else { return };
             let q = query.to_string();
 
             if q.is_empty() {
-                update_results(&ui, &state, &model, Vec::new());
+                update_results(&ui, &state, &model, &vulns, Vec::new());
                 return;
             }
 
@@ -144,7 +303,7 @@ fn main() -> Result<(), slint::PlatformError> {
 
             // Run search (blocking but fast for AUR; local for cached Flatpak/AppImage)
             let results = do_search(&q, aur, flatpak, appimage);
-            update_results(&ui, &state, &model, results);
+            update_results(&ui, &state, &model, &vulns, results);
         });
     }
 
@@ -153,6 +312,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let ui_weak = ui.as_weak();
         let state = state.clone();
         let model = model.clone();
+        let vulns = vulns.clone();
         ui.on_filter_changed(move || {
             let Some(ui) = ui_weak.upgrade() else { return };
             let q = ui.get_search_text().to_string();
@@ -165,7 +325,7 @@ fn main() -> Result<(), slint::PlatformError> {
             let appimage = ui.get_filter_appimage();
 
             let results = do_search(&q, aur, flatpak, appimage);
-            update_results(&ui, &state, &model, results);
+            update_results(&ui, &state, &model, &vulns, results);
         });
     }
 
@@ -184,6 +344,8 @@ fn main() -> Result<(), slint::PlatformError> {
             let app = &borrowed[idx];
             ui.set_selected_index(index);
             ui.set_install_status(SharedString::default());
+            ui.set_show_versions(false);
+            ui.set_app_pinned(app.installed && downgrade::is_pinned(&app.source, &app.id));
 
             // For Flatpak apps, fetch richer details
             if app.source == Source::Flatpak && !app.id.is_empty() {
@@ -200,86 +362,361 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
-    // -- Channel for async install/uninstall results --
-    // (index, is_install, success, message)
-    let (install_tx, install_rx) = std::sync::mpsc::channel::<(usize, bool, bool, String)>();
+    let (queue, done_rx) = queue::InstallQueue::start();
+    let queue = Rc::new(queue);
 
-    // -- Install app (async) --
+    // -- Install app (queued) --
     {
         let ui_weak = ui.as_weak();
         let state = state.clone();
-        let tx = install_tx.clone();
+        let queue = queue.clone();
         ui.on_install_app(move |index| {
             let Some(ui) = ui_weak.upgrade() else { return };
-            if ui.get_installing() { return; }
             let idx = index as usize;
             let borrowed = state.borrow();
-            if idx >= borrowed.len() { return; }
-
-            let app = borrowed[idx].clone();
+            let Some(app) = borrowed.get(idx).cloned() else { return };
             drop(borrowed);
+            if app.installed { return; }
 
-            ui.set_installing(true);
-            ui.set_install_status(SharedString::from(format!("Installing {}...", app.name)));
-
-            let tx = tx.clone();
-            std::thread::spawn(move || {
-                let result = installer::install(&app.source, &app.id);
-                let _ = tx.send((idx, true, result.success, result.message));
-            });
+            enqueue(&ui, &queue, Job::Install { app });
         });
     }
 
-    // -- Uninstall app (async) --
+    // -- Uninstall app (queued) --
     {
         let ui_weak = ui.as_weak();
         let state = state.clone();
-        let tx = install_tx.clone();
+        let queue = queue.clone();
         ui.on_uninstall_app(move |index| {
             let Some(ui) = ui_weak.upgrade() else { return };
-            if ui.get_installing() { return; }
             let idx = index as usize;
             let borrowed = state.borrow();
-            if idx >= borrowed.len() { return; }
-
-            let app = borrowed[idx].clone();
+            let Some(app) = borrowed.get(idx).cloned() else { return };
             drop(borrowed);
+            if !app.installed { return; }
+
+            enqueue(&ui, &queue, Job::Uninstall { app });
+        });
+    }
+
+    // -- Bundles: open list --
+    {
+        let ui_weak = ui.as_weak();
+        let bundles = bundles.clone();
+        ui.on_open_bundles(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let installed = catalog::installed_pacman_packages();
+            ui.set_bundles(ModelRc::new(VecModel::from(
+                bundles.iter().map(|e| to_bundle_item(e, &installed)).collect::<Vec<_>>(),
+            )));
+            ui.set_selected_bundle(-1);
+            ui.set_show_detail(false);
+            ui.set_show_cleanup(false);
+            ui.set_show_bundles(true);
+        });
+    }
+
+    // -- Bundles: select one, show its package checklist --
+    {
+        let ui_weak = ui.as_weak();
+        let bundles = bundles.clone();
+        ui.on_select_bundle(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let Some(edition) = bundles.get(index as usize) else { return };
+            let installed = catalog::installed_pacman_packages();
+            show_bundle_packages(&ui, edition, &installed);
+            ui.set_selected_bundle(index);
+        });
+    }
+
+    // -- Bundles: toggle a package in the checklist --
+    {
+        let ui_weak = ui.as_weak();
+        ui.on_toggle_bundle_package(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let packages = ui.get_bundle_packages();
+            if let Some(mut pkg) = packages.row_data(index as usize) {
+                if pkg.installed { return; }
+                pkg.checked = !pkg.checked;
+                packages.set_row_data(index as usize, pkg);
+            }
+        });
+    }
+
+    // -- Bundles: install the checked packages (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let bundles = bundles.clone();
+        let queue = queue.clone();
+        ui.on_install_bundle(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let Some(edition) = bundles.get(index as usize) else { return };
+
+            let (mut repo, mut aur) = (Vec::new(), Vec::new());
+            for pkg in ui.get_bundle_packages().iter() {
+                if !pkg.checked || pkg.installed { continue; }
+                if pkg.source == "AUR" {
+                    aur.push(pkg.name.to_string());
+                } else {
+                    repo.push(pkg.name.to_string());
+                }
+            }
+            if repo.is_empty() && aur.is_empty() {
+                ui.set_install_status("Nothing to install".into());
+                return;
+            }
+
+            let name = format!("{} edition", edition.name);
+            enqueue(&ui, &queue, Job::Bundle { name, repo, aur });
+        });
+    }
+
+    // -- Cleanup: scan and show --
+    {
+        let ui_weak = ui.as_weak();
+        let cleanup_items = cleanup_items.clone();
+        ui.on_open_cleanup(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            ui.set_show_detail(false);
+            ui.set_show_bundles(false);
+            ui.set_show_cleanup(true);
+            show_cleanup(&ui, &cleanup_items);
+        });
+    }
+
+    // -- Cleanup: toggle an item --
+    {
+        let ui_weak = ui.as_weak();
+        let cleanup_items = cleanup_items.clone();
+        ui.on_toggle_cleanup_item(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let entries = ui.get_cleanup_items();
+            if let Some(mut entry) = entries.row_data(index as usize) {
+                entry.checked = !entry.checked;
+                entries.set_row_data(index as usize, entry);
+            }
+            update_cleanup_total(&ui, &cleanup_items.borrow());
+        });
+    }
+
+    // -- Cleanup: remove the checked items (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let cleanup_items = cleanup_items.clone();
+        let queue = queue.clone();
+        ui.on_run_cleanup(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let entries = ui.get_cleanup_items();
+            let items: Vec<CleanupItem> = cleanup_items
+                .borrow()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| entries.row_data(*i).is_some_and(|e| e.checked))
+                .map(|(_, item)| item.clone())
+                .collect();
+            if items.is_empty() {
+                ui.set_install_status("Nothing selected".into());
+                return;
+            }
+            enqueue(&ui, &queue, Job::Cleanup { items });
+        });
+    }
+
+    // -- Versions: list installable versions of the selected app --
+    {
+        let ui_weak = ui.as_weak();
+        let state = state.clone();
+        let versions = versions.clone();
+        ui.on_open_versions(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let borrowed = state.borrow();
+            let Some(app) = borrowed.get(index as usize) else { return };
+            show_versions(&ui, app, &versions);
+            if versions.borrow().is_empty() {
+                ui.set_install_status(SharedString::from(format!(
+                    "No other versions of {} found",
+                    app.name
+                )));
+            }
+        });
+    }
+
+    // -- Versions: install one (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let state = state.clone();
+        let versions = versions.clone();
+        let queue = queue.clone();
+        ui.on_install_version(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let idx = ui.get_selected_index() as usize;
+            let Some(app) = state.borrow().get(idx).cloned() else { return };
+            let Some(version) = versions.borrow().get(index as usize).cloned() else { return };
+            if version.current { return; }
+            enqueue(&ui, &queue, Job::InstallVersion { app, version });
+        });
+    }
+
+    // -- Pin: hold back or release updates (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let state = state.clone();
+        let queue = queue.clone();
+        ui.on_toggle_pin(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let Some(app) = state.borrow().get(index as usize).cloned() else { return };
+            let pin = !ui.get_app_pinned();
+            enqueue(&ui, &queue, Job::Pin { app, pin });
+        });
+    }
+
+    // -- Security: load the advisory feed in the background --
+    {
+        let source = advisories::feed_source(&args);
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(advisories::load(&source));
+        });
+
+        let ui_weak = ui.as_weak();
+        let state = state.clone();
+        let model = model.clone();
+        let vulns = vulns.clone();
+        let advisory_groups = advisory_groups.clone();
+        let feed_timer = slint::Timer::default();
+        feed_timer.start(
+            slint::TimerMode::Repeated,
+            std::time::Duration::from_millis(250),
+            move || {
+                let Ok(groups) = rx.try_recv() else { return };
+                let Some(ui) = ui_weak.upgrade() else { return };
+                update_security(&ui, &groups, &vulns, &state, &model);
+                *advisory_groups.borrow_mut() = groups;
+            },
+        );
+        std::mem::forget(feed_timer);
+    }
 
-            ui.set_installing(true);
-            ui.set_install_status(SharedString::from(format!("Removing {}...", app.name)));
+    // -- Security: update one affected package (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let vulns = vulns.clone();
+        let queue = queue.clone();
+        ui.on_update_package(move |index| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let Some(vuln) = vulns.borrow().get(index as usize).cloned() else { return };
+            enqueue(&ui, &queue, Job::SecurityUpdate { packages: vec![vuln.package] });
+        });
+    }
 
-            let tx = tx.clone();
-            std::thread::spawn(move || {
-                let result = installer::uninstall(&app.source, &app.id, &app.name);
-                let _ = tx.send((idx, false, result.success, result.message));
-            });
+    // -- Security: update everything affected (queued) --
+    {
+        let ui_weak = ui.as_weak();
+        let vulns = vulns.clone();
+        let queue = queue.clone();
+        ui.on_update_all_vulnerable(move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let packages: Vec<String> = vulns
+                .borrow()
+                .iter()
+                .filter(|v| v.fixed.is_some())
+                .map(|v| v.package.clone())
+                .collect();
+            if packages.is_empty() {
+                ui.set_install_status("No fixes available yet".into());
+                return;
+            }
+            enqueue(&ui, &queue, Job::SecurityUpdate { packages });
         });
     }
 
-    // -- Poll install results from background thread --
+    // -- Poll finished jobs from the install queue --
     {
         let ui_weak = ui.as_weak();
         let state = state.clone();
         let model = model.clone();
+        let bundles = bundles.clone();
+        let cleanup_items = cleanup_items.clone();
+        let versions = versions.clone();
+        let vulns = vulns.clone();
+        let advisory_groups = advisory_groups.clone();
+        let queue = queue.clone();
         let poll_timer = slint::Timer::default();
         poll_timer.start(
             slint::TimerMode::Repeated,
             std::time::Duration::from_millis(100),
             move || {
-                while let Ok((idx, is_install, success, message)) = install_rx.try_recv() {
+                while let Ok(JobDone { job, result }) = done_rx.try_recv() {
                     let Some(ui) = ui_weak.upgrade() else { continue };
-                    ui.set_installing(false);
-                    ui.set_install_status(SharedString::from(&message));
-                    if success {
-                        let new_state = is_install;
-                        let mut borrowed = state.borrow_mut();
-                        if let Some(entry) = borrowed.get_mut(idx) {
-                            entry.installed = new_state;
+                    let left = queue.finish();
+                    ui.set_installing(left > 0);
+                    ui.set_install_status(SharedString::from(if left > 0 {
+                        format!("{} ({} more queued)", result.message, left)
+                    } else {
+                        result.message.clone()
+                    }));
+                    if !result.success {
+                        continue;
+                    }
+                    match job {
+                        Job::Install { ref app } | Job::Uninstall { ref app } => {
+                            let new_state = matches!(job, Job::Install { .. });
+                            // A search may have replaced the results meanwhile.
+                            let mut borrowed = state.borrow_mut();
+                            let Some(idx) = row_of(&borrowed, app) else { continue };
+                            borrowed[idx].installed = new_state;
+                            drop(borrowed);
+                            if let Some(mut item) = model.row_data(idx) {
+                                item.installed = new_state;
+                                model.set_row_data(idx, item);
+                            }
+                        }
+                        Job::Bundle { .. } => {
+                            let sel = ui.get_selected_bundle();
+                            if let Some(edition) = bundles.get(sel as usize) {
+                                let installed = catalog::installed_pacman_packages();
+                                show_bundle_packages(&ui, edition, &installed);
+                                ui.set_bundles(ModelRc::new(VecModel::from(
+                                    bundles
+                                        .iter()
+                                        .map(|e| to_bundle_item(e, &installed))
+                                        .collect::<Vec<_>>(),
+                                )));
+                            }
+                        }
+                        Job::Cleanup { .. } => {
+                            if ui.get_show_cleanup() {
+                                show_cleanup(&ui, &cleanup_items);
+                            }
                         }
-                        drop(borrowed);
-                        if let Some(mut item) = model.row_data(idx) {
-                            item.installed = new_state;
+                        Job::InstallVersion { app, version } => {
+                            let mut borrowed = state.borrow_mut();
+                            let Some(idx) = row_of(&borrowed, &app) else { continue };
+                            let entry = &mut borrowed[idx];
+                            if let downgrade::VersionTarget::Package(_) = version.target {
+                                entry.version = version.label.clone();
+                            }
+                            let item = to_ui_item(entry, &vulns.borrow());
+                            drop(borrowed);
                             model.set_row_data(idx, item);
+                            if ui.get_show_versions() && ui.get_selected_index() as usize == idx {
+                                show_versions(&ui, &app, &versions);
+                            }
+                        }
+                        Job::SecurityUpdate { .. } => {
+                            update_security(
+                                &ui,
+                                &advisory_groups.borrow(),
+                                &vulns,
+                                &state,
+                                &model,
+                            );
+                        }
+                        Job::Pin { app, pin } => {
+                            let sel = ui.get_selected_index() as usize;
+                            if state.borrow().get(sel).is_some_and(|a| a.id == app.id) {
+                                ui.set_app_pinned(pin);
+                            }
                         }
                     }
                 }
@@ -354,6 +791,41 @@ fn main() -> Result<(), slint::PlatformError> {
         std::mem::forget(timer);
     }
 
+    // -- Deep link: open on an app's detail page once it's looked up --
+    // The lookups can take seconds, so the window shows first.
     ui.invoke_focus_search();
+    if let Some(link) = deeplink::DeepLink::from_args(&args) {
+        ui.set_status_text(SharedString::from(format!("Opening {}...", link.id())));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let id = link.id().to_string();
+        std::thread::spawn(move || {
+            let _ = tx.send(link.resolve());
+        });
+
+        let ui_weak = ui.as_weak();
+        let state = state.clone();
+        let model = model.clone();
+        let vulns = vulns.clone();
+        let link_timer = slint::Timer::default();
+        link_timer.start(
+            slint::TimerMode::Repeated,
+            std::time::Duration::from_millis(100),
+            move || {
+                let Ok(found) = rx.try_recv() else { return };
+                let Some(ui) = ui_weak.upgrade() else { return };
+                match found {
+                    Some(app) => {
+                        ui.set_search_text(SharedString::from(&app.name));
+                        update_results(&ui, &state, &model, &vulns, vec![app]);
+                        ui.invoke_select_app(0);
+                        ui.invoke_focus_list();
+                    }
+                    None => ui.set_status_text(SharedString::from(format!("App not found: {id}"))),
+                }
+            },
+        );
+        std::mem::forget(link_timer);
+    }
+
     ui.run()
 }
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// pacman's package cache.
+pub const PACMAN_CACHE: &str = "/var/cache/pacman/pkg";
+
+/// A built package file found in a cache directory.
+#[derive(Clone, Debug)]
+pub struct CachedPackage {
+    pub path: PathBuf,
+    pub name: String,
+    /// `pkgver-pkgrel`, with epoch if any.
+    pub version: String,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// paru's clone directory (one sub-directory per AUR package).
+pub fn paru_cache() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(home).join(".cache").join("paru").join("clone")
+}
+
+/// paru's clone of the AUR package `name`. Clones are named by pkgbase,
+/// so split packages are found through `base` (from `pacman -Qi`) or the
+/// clones' .SRCINFO.
+pub fn paru_clone(name: &str, base: Option<&str>) -> Option<PathBuf> {
+    let dir = paru_cache();
+    let named = [base, Some(name)].into_iter().flatten().map(|b| dir.join(b));
+    if let Some(clone) = named.into_iter().find(|d| d.is_dir()) {
+        return Some(clone);
+    }
+    fs::read_dir(&dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .find(|p| {
+            let srcinfo = fs::read_to_string(p.join(".SRCINFO")).unwrap_or_default();
+            srcinfo_pkgnames(&srcinfo).contains(&name)
+        })
+}
+
+/// `pkgname = ...` entries of a .SRCINFO: every package the clone builds.
+pub fn srcinfo_pkgnames(srcinfo: &str) -> Vec<&str> {
+    srcinfo
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pkgname"))
+        .filter_map(|rest| rest.trim_start().strip_prefix('='))
+        .map(str::trim)
+        .collect()
+}
+
+/// Split a package file name (`name-pkgver-pkgrel-arch.pkg.tar.zst`) into
+/// `(name, "pkgver-pkgrel")`. Signatures and partial downloads are rejected.
+pub fn parse_pkg_filename(file: &str) -> Option<(String, String)> {
+    let idx = file.find(".pkg.tar")?;
+    let rest = &file[idx..];
+    if rest.ends_with(".sig") || rest.ends_with(".part") {
+        return None;
+    }
+    let stem = &file[..idx];
+
+    // The last three dash-separated fields are pkgver, pkgrel and arch;
+    // everything before them is the name (which may contain dashes).
+    let mut parts = stem.rsplitn(4, '-');
+    let _arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), format!("{}-{}", pkgver, pkgrel)))
+}
+
+/// List package files directly inside `dir`.
+pub fn scan_dir(dir: &Path) -> Vec<CachedPackage> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let file = entry.file_name().to_string_lossy().to_string();
+            let (name, version) = parse_pkg_filename(&file)?;
+            let meta = entry.metadata().ok()?;
+            Some(CachedPackage {
+                path: entry.path(),
+                name,
+                version,
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect()
+}
+
+/// List package files built by paru (`<clone>/<pkg>/*.pkg.tar*`).
+pub fn scan_paru() -> Vec<CachedPackage> {
+    let Ok(entries) = fs::read_dir(paru_cache()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .flat_map(|e| scan_dir(&e.path()))
+        .collect()
+}
+
+/// Package files beyond the `keep` most recent ones for each package name.
+pub fn old_versions(packages: Vec<CachedPackage>, keep: usize) -> Vec<CachedPackage> {
+    let mut by_name: HashMap<String, Vec<CachedPackage>> = HashMap::new();
+    for pkg in packages {
+        by_name.entry(pkg.name.clone()).or_default().push(pkg);
+    }
+    let mut old = Vec::new();
+    for mut versions in by_name.into_values() {
+        versions.sort_by_key(|p| std::cmp::Reverse(p.modified));
+        old.extend(versions.into_iter().skip(keep));
+    }
+    old
+}
+
+/// Total size of a file or directory tree in bytes.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_usage(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Human-readable size, e.g. "1.4 GiB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srcinfo_lists_split_packages() {
+        let srcinfo = "pkgbase = linux-zen-git\n\tpkgver = 6.8\n\tpkgdesc = Zen kernel\n\n\
+            pkgname = linux-zen-git\n\npkgname = linux-zen-git-headers\n\tpkgdesc = Headers\n";
+        assert_eq!(srcinfo_pkgnames(srcinfo), ["linux-zen-git", "linux-zen-git-headers"]);
+    }
+
+    #[test]
+    fn package_file_names() {
+        assert_eq!(
+            parse_pkg_filename("python-foo-bar-1:2.0.1-3-x86_64.pkg.tar.zst"),
+            Some(("python-foo-bar".into(), "1:2.0.1-3".into()))
+        );
+        assert_eq!(parse_pkg_filename("vim-9.1-1-x86_64.pkg.tar.zst.sig"), None);
+        assert_eq!(parse_pkg_filename("vim-9.1-1-x86_64.pkg.tar.zst.part"), None);
+        assert_eq!(parse_pkg_filename("README"), None);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::catalog::AppEntry;
+use crate::cleanup::{self, CleanupItem};
+use crate::downgrade::{self, Version};
+use crate::installer::{self, InstallResult};
+use std::cell::Cell;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// A unit of work for the install queue.
+pub enum Job {
+    /// Install a search result.
+    Install { app: AppEntry },
+    /// Remove a search result.
+    Uninstall { app: AppEntry },
+    /// Install the selected packages of an edition bundle.
+    Bundle {
+        name: String,
+        repo: Vec<String>,
+        aur: Vec<String>,
+    },
+    /// Remove the selected cleanup items.
+    Cleanup { items: Vec<CleanupItem> },
+    /// Install an older (or newer) version of an installed app.
+    InstallVersion { app: AppEntry, version: Version },
+    /// Hold back or release updates for an app.
+    Pin { app: AppEntry, pin: bool },
+    /// Upgrade the system to pick up security fixes for `packages`.
+    SecurityUpdate { packages: Vec<String> },
+}
+
+impl Job {
+    /// Status line shown while the job runs.
+    pub fn label(&self) -> String {
+        match self {
+            Job::Install { app, .. } => format!("Installing {}...", app.name),
+            Job::Uninstall { app, .. } => format!("Removing {}...", app.name),
+            Job::Bundle { name, repo, aur } => {
+                format!("Installing {} ({} packages)...", name, repo.len() + aur.len())
+            }
+            Job::Cleanup { items } => format!("Cleaning up {} items...", items.len()),
+            Job::InstallVersion { app, version, .. } => {
+                format!("Installing {} {}...", app.name, version.label)
+            }
+            Job::Pin { app, pin: true } => format!("Holding updates for {}...", app.name),
+            Job::Pin { app, pin: false } => format!("Releasing {}...", app.name),
+            Job::SecurityUpdate { packages } => {
+                format!("Upgrading system to fix {}...", packages.join(", "))
+            }
+        }
+    }
+
+    fn run(&self) -> InstallResult {
+        match self {
+            Job::Install { app, .. } => installer::install(&app.source, &app.id),
+            Job::Uninstall { app, .. } => installer::uninstall(&app.source, &app.id, &app.name),
+            Job::Bundle { repo, aur, .. } => {
+                // Repo packages first so AUR builds find their dependencies.
+                if !repo.is_empty() {
+                    let result = installer::install_repo_packages(repo);
+                    if !result.success {
+                        return result;
+                    }
+                }
+                if !aur.is_empty() {
+                    return installer::install_aur_packages(aur);
+                }
+                InstallResult {
+                    success: true,
+                    message: "Installed successfully".into(),
+                }
+            }
+            Job::Cleanup { items } => cleanup::remove(items),
+            Job::InstallVersion { app, version, .. } => {
+                downgrade::install_version(&app.id, &version.target)
+            }
+            Job::Pin { app, pin } => downgrade::set_pinned(&app.source, &app.id, *pin),
+            Job::SecurityUpdate { .. } => installer::upgrade_system(),
+        }
+    }
+}
+
+/// A finished job and its outcome.
+pub struct JobDone {
+    pub job: Job,
+    pub result: InstallResult,
+}
+
+/// Runs install jobs one at a time on a background thread, so pacman,
+/// paru and flatpak never fight over their locks.
+pub struct InstallQueue {
+    tx: Sender<Job>,
+    pending: Cell<usize>,
+}
+
+impl InstallQueue {
+    /// Start the worker thread. Finished jobs arrive on the returned receiver.
+    pub fn start() -> (Self, Receiver<JobDone>) {
+        let (tx, job_rx) = channel::<Job>();
+        let (done_tx, done_rx) = channel::<JobDone>();
+
+        std::thread::spawn(move || {
+            for job in job_rx {
+                let result = job.run();
+                if done_tx.send(JobDone { job, result }).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let queue = Self {
+            tx,
+            pending: Cell::new(0),
+        };
+        (queue, done_rx)
+    }
+
+    pub fn push(&self, job: Job) {
+        if self.tx.send(job).is_ok() {
+            self.pending.set(self.pending.get() + 1);
+        }
+    }
+
+    /// Mark one job as finished. Returns the number still queued or running.
+    pub fn finish(&self) -> usize {
+        let left = self.pending.get().saturating_sub(1);
+        self.pending.set(left);
+        left
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.get()
+    }
+}
@@ -35,27 +35,37 @@ pub fn search(query: &str) -> Vec<AppEntry> {
                 || app.description.to_lowercase().contains(&q)
         })
         .take(50)
-        .map(|app| {
-            let installed = is_appimage_installed(&app.name);
-            let icon_url = app.icons.first().cloned().unwrap_or_default();
-            let homepage = app.links.first().cloned().unwrap_or_default();
-            AppEntry {
-                name: app.name.clone(),
-                id: app.name,
-                version: String::new(),
-                description: strip_html(&app.description),
-                source: Source::AppImage,
-                icon_url,
-                icon_path: String::new(),
-                homepage,
-                votes: 0,
-                popularity: 0.0,
-                installed,
-            }
-        })
+        .map(to_entry)
         .collect()
 }
 
+/// Look up a single AppImage by exact name (case-insensitive).
+pub fn info(name: &str) -> Option<AppEntry> {
+    load_catalog()
+        .into_iter()
+        .find(|app| app.name.eq_ignore_ascii_case(name))
+        .map(to_entry)
+}
+
+fn to_entry(app: AppImageEntry) -> AppEntry {
+    let installed = is_appimage_installed(&app.name);
+    let icon_url = app.icons.first().cloned().unwrap_or_default();
+    let homepage = app.links.first().cloned().unwrap_or_default();
+    AppEntry {
+        name: app.name.clone(),
+        id: app.name,
+        version: String::new(),
+        description: strip_html(&app.description),
+        source: Source::AppImage,
+        icon_url,
+        icon_path: String::new(),
+        homepage,
+        votes: 0,
+        popularity: 0.0,
+        installed,
+    }
+}
+
 /// Load the AppImage catalog, refreshing from network if stale.
 fn load_catalog() -> Vec<AppImageEntry> {
     let cache_path = cache_dir().join("appimage-catalog.json");
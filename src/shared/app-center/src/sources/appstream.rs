@@ -0,0 +1,518 @@
+use crate::catalog::{is_flatpak_installed, strip_html, AppEntry, Source};
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Icon sizes to look for in the appstream icon cache, best first.
+const ICON_SIZES: [&str; 3] = ["128x128", "64x64", "256x256"];
+
+/// Catalog built from the local appstream data, loaded on first use.
+static INDEX: OnceLock<Vec<Indexed>> = OnceLock::new();
+
+/// An app from the local catalog with its lowercased search fields.
+struct Indexed {
+    entry: AppEntry,
+    /// Full `<description>`, shown on the detail page.
+    details: String,
+    name: String,
+    id: String,
+    summary: String,
+}
+
+/// Search the appstream data Flatpak keeps for its remotes.
+/// Works without network access; name matches rank above summary matches.
+pub fn search(query: &str) -> Vec<AppEntry> {
+    if query.len() < 2 {
+        return Vec::new();
+    }
+
+    ranked(index(), &query.to_lowercase())
+        .into_iter()
+        .take(50)
+        .map(|app| {
+            let mut entry = app.entry.clone();
+            entry.installed = is_flatpak_installed(&entry.id);
+            entry
+        })
+        .collect()
+}
+
+/// Apps matching the lowercased query `q`, best first: exact name, name
+/// prefix, name or id substring, then summary.
+fn ranked<'a>(index: &'a [Indexed], q: &str) -> Vec<&'a Indexed> {
+    let mut hits: Vec<(u8, &Indexed)> = index
+        .iter()
+        .filter_map(|app| {
+            let rank = if app.name == q {
+                0
+            } else if app.name.starts_with(q) {
+                1
+            } else if app.name.contains(q) || app.id.contains(q) {
+                2
+            } else if app.summary.contains(q) {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, app))
+        })
+        .collect();
+    hits.sort_by_key(|(rank, _)| *rank);
+    hits.into_iter().map(|(_, app)| app).collect()
+}
+
+/// Look up one app by its Flatpak id, with the full description.
+pub fn get_details(app_id: &str) -> Option<AppEntry> {
+    let app = index().iter().find(|app| app.entry.id == app_id)?;
+    let mut entry = app.entry.clone();
+    entry.installed = is_flatpak_installed(&entry.id);
+    if !app.details.is_empty() {
+        entry.description = app.details.clone();
+    }
+    Some(entry)
+}
+
+fn index() -> &'static [Indexed] {
+    INDEX.get_or_init(|| {
+        let langs = user_languages();
+        let mut apps: Vec<Indexed> = Vec::new();
+        let mut seen = HashSet::new();
+        for dir in appstream_dirs() {
+            let Some(xml) = read_gz(&dir.join("appstream.xml.gz")) else { continue };
+            for (entry, details) in parse(&xml, &dir, &langs) {
+                // The same app can be offered by several remotes.
+                if seen.insert(entry.id.clone()) {
+                    apps.push(Indexed::new(entry, details));
+                }
+            }
+        }
+        apps
+    })
+}
+
+impl Indexed {
+    fn new(entry: AppEntry, details: String) -> Self {
+        Self {
+            name: entry.name.to_lowercase(),
+            id: entry.id.to_lowercase(),
+            summary: entry.description.to_lowercase(),
+            entry,
+            details,
+        }
+    }
+}
+
+/// The user's languages, most preferred first, as appstream writes them
+/// in `xml:lang`: `LANGUAGE`'s list, else the messages locale, each
+/// followed by its bare language ("pt_BR", "pt").
+fn user_languages() -> Vec<String> {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let list = var("LANGUAGE")
+        .or_else(|| var("LC_ALL"))
+        .or_else(|| var("LC_MESSAGES"))
+        .or_else(|| var("LANG"))
+        .unwrap_or_default();
+    languages(&list)
+}
+
+/// Expand a colon-separated locale list ("de_DE.UTF-8:fr") into
+/// appstream language tags.
+fn languages(list: &str) -> Vec<String> {
+    let mut langs: Vec<String> = Vec::new();
+    for locale in list.split(':') {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        if locale.is_empty() || locale == "C" || locale == "POSIX" {
+            continue;
+        }
+        let bare = locale.split('_').next().unwrap_or(locale);
+        for lang in [locale, bare] {
+            if !langs.iter().any(|l| l == lang) {
+                langs.push(lang.to_string());
+            }
+        }
+    }
+    langs
+}
+
+/// `<installation>/appstream/<remote>/<arch>/active` for the user and
+/// system installations. Flathub comes first.
+fn appstream_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let installations = [
+        PathBuf::from(home).join(".local/share/flatpak"),
+        PathBuf::from("/var/lib/flatpak"),
+    ];
+
+    let mut dirs = Vec::new();
+    for inst in installations {
+        let Ok(remotes) = std::fs::read_dir(inst.join("appstream")) else { continue };
+        let mut remotes: Vec<PathBuf> = remotes.flatten().map(|e| e.path()).collect();
+        remotes.sort_by_key(|p| p.file_name().is_none_or(|n| n != "flathub"));
+        for remote in remotes {
+            let active = remote.join(std::env::consts::ARCH).join("active");
+            if active.join("appstream.xml.gz").exists() {
+                dirs.push(active);
+            }
+        }
+    }
+    dirs
+}
+
+fn read_gz(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut xml = String::new();
+    GzDecoder::new(file).read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+/// Parse an appstream catalog into entries and their full descriptions,
+/// translated to the first of `langs` available. `dir` holds the `icons/`
+/// cache next to the catalog.
+fn parse(xml: &str, dir: &Path, langs: &[String]) -> Vec<(AppEntry, String)> {
+    let doc = match Document::parse(xml) {
+        Ok(doc) => doc,
+        Err(e) => {
+            eprintln!("appstream parse error: {}", e);
+            return Vec::new();
+        }
+    };
+
+    doc.root_element()
+        .children()
+        .filter(|n| n.has_tag_name("component"))
+        .filter(|n| matches!(n.attribute("type"), Some("desktop" | "desktop-application")))
+        .filter_map(|n| to_entry(n, dir, langs))
+        .collect()
+}
+
+fn to_entry(component: Node, dir: &Path, langs: &[String]) -> Option<(AppEntry, String)> {
+    let id = flatpak_id(component)?;
+    let name = localized(component, "name", langs).map(text).unwrap_or_else(|| id.clone());
+    let summary = localized(component, "summary", langs).map(text).unwrap_or_default();
+    let details = description(component, langs);
+
+    let homepage = component
+        .children()
+        .find(|n| n.has_tag_name("url") && n.attribute("type") == Some("homepage"))
+        .map(text)
+        .unwrap_or_default();
+
+    let version = component
+        .children()
+        .find(|n| n.has_tag_name("releases"))
+        .and_then(|r| r.children().find(|n| n.has_tag_name("release")))
+        .and_then(|r| r.attribute("version"))
+        .unwrap_or_default()
+        .to_string();
+
+    let icon_url = component
+        .children()
+        .find(|n| n.has_tag_name("icon") && n.attribute("type") == Some("remote"))
+        .map(text)
+        .unwrap_or_default();
+
+    let entry = AppEntry {
+        name,
+        id,
+        version,
+        description: summary,
+        source: Source::Flatpak,
+        icon_url,
+        icon_path: cached_icon(component, dir),
+        homepage,
+        votes: 0,
+        popularity: 0.0,
+        installed: false,
+    };
+    Some((entry, details))
+}
+
+/// The app id from `<bundle type="flatpak">app/ID/arch/branch</bundle>`,
+/// falling back to `<id>` without a `.desktop` suffix.
+fn flatpak_id(component: Node) -> Option<String> {
+    let bundle = component
+        .children()
+        .find(|n| n.has_tag_name("bundle") && n.attribute("type") == Some("flatpak"))
+        .and_then(|n| n.text())
+        .and_then(|t| t.trim().split('/').nth(1).map(str::to_string));
+    if bundle.is_some() {
+        return bundle;
+    }
+    let id = component.children().find(|n| n.has_tag_name("id")).map(text)?;
+    Some(id.strip_suffix(".desktop").unwrap_or(&id).to_string())
+}
+
+/// Child with the given tag in the first of `langs` it's translated to,
+/// else the untranslated one.
+fn localized<'a, 'i>(component: Node<'a, 'i>, tag: &str, langs: &[String]) -> Option<Node<'a, 'i>> {
+    let in_lang = |lang: Option<&str>| {
+        component
+            .children()
+            .find(|n| n.has_tag_name(tag) && n.attribute((XML_NS, "lang")) == lang)
+    };
+    langs.iter().find_map(|l| in_lang(Some(l))).or_else(|| in_lang(None))
+}
+
+/// All text inside a node, whitespace collapsed.
+fn text(node: Node) -> String {
+    let raw: Vec<&str> = node
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    strip_html(&raw.concat())
+}
+
+/// Paragraphs and list items of the `<description>` in the first of
+/// `langs` it's translated to, else the untranslated ones. Catalogs
+/// translate either whole descriptions or each paragraph.
+fn description(component: Node, langs: &[String]) -> String {
+    let descriptions: Vec<Node> = component.children().filter(|n| n.has_tag_name("description")).collect();
+    let in_lang = |lang: Option<&str>| -> Vec<String> {
+        descriptions
+            .iter()
+            .flat_map(|d| {
+                let whole = d.attribute((XML_NS, "lang"));
+                d.descendants()
+                    .filter(|n| n.has_tag_name("p") || n.has_tag_name("li"))
+                    .filter(move |n| n.attribute((XML_NS, "lang")).or(whole) == lang)
+            })
+            .map(text)
+            .collect()
+    };
+    let parts = langs
+        .iter()
+        .map(|l| in_lang(Some(l)))
+        .find(|parts| !parts.is_empty())
+        .unwrap_or_else(|| in_lang(None));
+    parts.join(" ")
+}
+
+/// Path of the cached icon under `<dir>/icons/<size>/`, if present.
+fn cached_icon(component: Node, dir: &Path) -> String {
+    let Some(file) = component
+        .children()
+        .find(|n| n.has_tag_name("icon") && n.attribute("type") == Some("cached"))
+        .map(text)
+    else {
+        return String::new();
+    };
+    ICON_SIZES
+        .iter()
+        .map(|size| dir.join("icons").join(size).join(&file))
+        .find(|p| p.exists())
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const CATALOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<components version="0.14" origin="flathub">
+  <component type="desktop-application">
+    <id>org.gimp.GIMP</id>
+    <name>GNU Image Manipulation Program</name>
+    <name xml:lang="de">GNU-Bildbearbeitungsprogramm</name>
+    <summary>Create images and edit photographs</summary>
+    <summary xml:lang="fr">Créer des images et éditer des photographies</summary>
+    <description>
+      <p>GIMP is an advanced picture editor.</p>
+      <p xml:lang="de">GIMP ist ein fortgeschrittenes Bildbearbeitungsprogramm.</p>
+      <ul>
+        <li>Layers and channels</li>
+        <li xml:lang="de">Ebenen und Kanäle</li>
+      </ul>
+    </description>
+    <icon type="cached" width="64" height="64">org.gimp.GIMP.png</icon>
+    <icon type="remote" width="128" height="128">https://dl.flathub.org/media/org/gimp/GIMP/icons/128x128/org.gimp.GIMP.png</icon>
+    <url type="homepage">https://www.gimp.org/</url>
+    <bundle type="flatpak" runtime="org.gnome.Platform/x86_64/46" sdk="org.gnome.Sdk/x86_64/46">app/org.gimp.GIMP/x86_64/stable</bundle>
+    <releases>
+      <release version="2.10.38" timestamp="1714694400"/>
+      <release version="2.10.36" timestamp="1699228800"/>
+    </releases>
+  </component>
+  <component type="desktop">
+    <id>org.kde.krita.desktop</id>
+    <name>Krita</name>
+    <summary>Digital Painting, Creative Freedom</summary>
+    <description xml:lang="de"><p>Krita ist ein Malprogramm.</p></description>
+    <description><p>Krita is a painting program.</p></description>
+    <icon type="cached">org.kde.krita.png</icon>
+  </component>
+  <component type="desktop-application">
+    <id>io.github.nicotine.Gimpy</id>
+    <name>Gimpy</name>
+    <summary>Chat client</summary>
+    <bundle type="flatpak">app/io.github.nicotine.Gimpy/x86_64/stable</bundle>
+  </component>
+  <component type="desktop-application">
+    <id>org.inkscape.Inkscape</id>
+    <name>Inkscape</name>
+    <summary>Vector graphics editor, a GIMP companion</summary>
+    <bundle type="flatpak">app/org.inkscape.Inkscape/x86_64/stable</bundle>
+  </component>
+  <component type="desktop-application">
+    <id>org.example.Photogimp</id>
+    <name>Photogimp</name>
+    <summary>Photoshop-like layout</summary>
+    <bundle type="flatpak">app/org.example.Photogimp/x86_64/stable</bundle>
+  </component>
+  <component type="desktop-application">
+    <id>org.example.Gimp</id>
+    <name>gimp</name>
+    <bundle type="flatpak">app/org.example.Gimp/x86_64/stable</bundle>
+  </component>
+  <component type="runtime">
+    <id>org.gnome.Platform</id>
+    <name>GNOME Application Platform</name>
+  </component>
+  <component type="console-application">
+    <id>org.example.gimp-cli</id>
+    <name>gimp-cli</name>
+  </component>
+</components>
+"#;
+
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("app-center-appstream-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn langs(list: &[&str]) -> Vec<String> {
+        list.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn find<'a>(apps: &'a [(AppEntry, String)], id: &str) -> &'a (AppEntry, String) {
+        apps.iter().find(|(e, _)| e.id == id).unwrap()
+    }
+
+    #[test]
+    fn ids_from_flatpak_bundles_and_desktop_ids() {
+        let apps = parse(CATALOG, Path::new("/nonexistent"), &[]);
+        let ids: Vec<&str> = apps.iter().map(|(e, _)| e.id.as_str()).collect();
+        // Runtimes and console apps aren't listed.
+        assert_eq!(
+            ids,
+            [
+                "org.gimp.GIMP",
+                "org.kde.krita",
+                "io.github.nicotine.Gimpy",
+                "org.inkscape.Inkscape",
+                "org.example.Photogimp",
+                "org.example.Gimp"
+            ]
+        );
+        let (gimp, _) = find(&apps, "org.gimp.GIMP");
+        assert_eq!(gimp.version, "2.10.38");
+        assert_eq!(gimp.homepage, "https://www.gimp.org/");
+        assert_eq!(gimp.source, Source::Flatpak);
+        assert!(gimp.icon_url.ends_with("/128x128/org.gimp.GIMP.png"));
+    }
+
+    #[test]
+    fn untranslated_without_a_locale() {
+        let apps = parse(CATALOG, Path::new("/nonexistent"), &[]);
+        let (gimp, details) = find(&apps, "org.gimp.GIMP");
+        assert_eq!(gimp.name, "GNU Image Manipulation Program");
+        assert_eq!(gimp.description, "Create images and edit photographs");
+        assert_eq!(details, "GIMP is an advanced picture editor. Layers and channels");
+        let (krita, details) = find(&apps, "org.kde.krita");
+        assert_eq!(details, "Krita is a painting program.");
+        assert_eq!(krita.name, "Krita");
+    }
+
+    #[test]
+    fn translations_fall_back_per_field() {
+        let apps = parse(CATALOG, Path::new("/nonexistent"), &langs(&["de_AT", "de"]));
+        let (gimp, details) = find(&apps, "org.gimp.GIMP");
+        assert_eq!(gimp.name, "GNU-Bildbearbeitungsprogramm");
+        // No German summary: the untranslated one, not the French.
+        assert_eq!(gimp.description, "Create images and edit photographs");
+        assert_eq!(details, "GIMP ist ein fortgeschrittenes Bildbearbeitungsprogramm. Ebenen und Kanäle");
+        // Whole translated descriptions work too.
+        let (_, details) = find(&apps, "org.kde.krita");
+        assert_eq!(details, "Krita ist ein Malprogramm.");
+
+        let apps = parse(CATALOG, Path::new("/nonexistent"), &langs(&["fr_FR", "fr"]));
+        let (gimp, _) = find(&apps, "org.gimp.GIMP");
+        assert_eq!(gimp.name, "GNU Image Manipulation Program");
+        assert_eq!(gimp.description, "Créer des images et éditer des photographies");
+    }
+
+    #[test]
+    fn locale_lists() {
+        assert_eq!(languages("de_DE.UTF-8"), ["de_DE", "de"]);
+        assert_eq!(languages("pt_BR:pt:en_US"), ["pt_BR", "pt", "en_US", "en"]);
+        assert_eq!(languages("sr_RS@latin"), ["sr_RS", "sr"]);
+        assert!(languages("C.UTF-8").is_empty());
+        assert!(languages("POSIX").is_empty());
+        assert!(languages("").is_empty());
+    }
+
+    #[test]
+    fn icons_come_from_the_cache_next_to_the_catalog() {
+        let dir = scratch_dir("icons");
+        for size in ["64x64", "128x128"] {
+            std::fs::create_dir_all(dir.join("icons").join(size)).unwrap();
+            std::fs::write(dir.join("icons").join(size).join("org.gimp.GIMP.png"), b"png").unwrap();
+        }
+        std::fs::write(dir.join("icons/64x64/org.kde.krita.png"), b"png").unwrap();
+
+        let apps = parse(CATALOG, &dir, &[]);
+        let icon = |id| PathBuf::from(&find(&apps, id).0.icon_path);
+        // 128x128 is preferred over 64x64 (see ICON_SIZES).
+        assert_eq!(icon("org.gimp.GIMP"), dir.join("icons/128x128/org.gimp.GIMP.png"));
+        assert_eq!(icon("org.kde.krita"), dir.join("icons/64x64/org.kde.krita.png"));
+        // No cached icon, or not in the cache.
+        assert_eq!(icon("org.inkscape.Inkscape"), PathBuf::new());
+    }
+
+    #[test]
+    fn reads_gzipped_catalogs() {
+        let dir = scratch_dir("gzip");
+        let path = dir.join("appstream.xml.gz");
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(CATALOG.as_bytes()).unwrap();
+        std::fs::write(&path, gz.finish().unwrap()).unwrap();
+
+        let xml = read_gz(&path).unwrap();
+        assert_eq!(parse(&xml, &dir, &[]).len(), 6);
+        assert_eq!(read_gz(&dir.join("missing.xml.gz")), None);
+        assert!(parse("<components><component", &dir, &[]).is_empty());
+    }
+
+    #[test]
+    fn search_ranks_names_before_summaries() {
+        let index: Vec<Indexed> = parse(CATALOG, Path::new("/nonexistent"), &[])
+            .into_iter()
+            .map(|(entry, details)| Indexed::new(entry, details))
+            .collect();
+        let ids = |q: &str| -> Vec<String> { ranked(&index, q).iter().map(|a| a.entry.id.clone()).collect() };
+
+        assert_eq!(
+            ids("gimp"),
+            [
+                // Exact name, name prefix, name or id substring, summary.
+                "org.example.Gimp",
+                "io.github.nicotine.Gimpy",
+                "org.gimp.GIMP",
+                "org.example.Photogimp",
+                "org.inkscape.Inkscape",
+            ]
+        );
+        assert_eq!(ids("painting"), ["org.kde.krita"]);
+        assert!(ids("blender").is_empty());
+    }
+}
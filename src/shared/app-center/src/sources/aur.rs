@@ -44,26 +44,35 @@ pub fn search(query: &str) -> Vec<AppEntry> {
         Err(_) => return Vec::new(),
     };
 
-    body.results
-        .into_iter()
-        .take(50)
-        .map(|pkg| {
-            let installed = is_pacman_installed(&pkg.name);
-            AppEntry {
-                name: pkg.name.clone(),
-                id: pkg.name,
-                version: pkg.version,
-                description: pkg.description.unwrap_or_default(),
-                source: Source::Aur,
-                icon_url: String::new(),
-                icon_path: String::new(),
-                homepage: pkg.url.unwrap_or_default(),
-                votes: pkg.num_votes.unwrap_or(0),
-                popularity: pkg.popularity.unwrap_or(0.0),
-                installed,
-            }
-        })
-        .collect()
+    body.results.into_iter().take(50).map(to_entry).collect()
+}
+
+/// Look up a single AUR package by exact name.
+pub fn info(name: &str) -> Option<AppEntry> {
+    let url = format!("https://aur.archlinux.org/rpc/v5/info?arg[]={}", urlenc(name));
+    let resp = ureq::get(&url)
+        .timeout(std::time::Duration::from_secs(5))
+        .call()
+        .ok()?;
+    let body: AurResponse = resp.into_json().ok()?;
+    body.results.into_iter().next().map(to_entry)
+}
+
+fn to_entry(pkg: AurPackage) -> AppEntry {
+    let installed = is_pacman_installed(&pkg.name);
+    AppEntry {
+        name: pkg.name.clone(),
+        id: pkg.name,
+        version: pkg.version,
+        description: pkg.description.unwrap_or_default(),
+        source: Source::Aur,
+        icon_url: String::new(),
+        icon_path: String::new(),
+        homepage: pkg.url.unwrap_or_default(),
+        votes: pkg.num_votes.unwrap_or(0),
+        popularity: pkg.popularity.unwrap_or(0.0),
+        installed,
+    }
 }
 
 /// Minimal URL encoding for the search query.
@@ -1,7 +1,17 @@
+use super::appstream;
 use crate::catalog::{is_flatpak_installed, strip_html, AppEntry, Source};
 use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+/// Skip the Flathub API and answer from the local appstream data.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Prefer the local appstream catalog over the network (`--offline`).
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
 /// A hit from the Flathub POST /search API.
 #[derive(Deserialize)]
 struct SearchHit {
@@ -41,11 +51,15 @@ struct FlathubRelease {
     version: Option<String>,
 }
 
-/// Search the full Flathub catalog via the server-side search API.
+/// Search the full Flathub catalog via the server-side search API,
+/// falling back to the local appstream data when offline.
 pub fn search(query: &str) -> Vec<AppEntry> {
     if query.len() < 2 {
         return Vec::new();
     }
+    if OFFLINE.load(Ordering::Relaxed) {
+        return appstream::search(query);
+    }
 
     let body = format!(r#"{{"query":"{}"}}"#, query.replace('"', r#"\""#));
 
@@ -56,8 +70,8 @@ pub fn search(query: &str) -> Vec<AppEntry> {
     {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Flathub search failed: {}", e);
-            return Vec::new();
+            eprintln!("Flathub search failed, using local appstream data: {}", e);
+            return appstream::search(query);
         }
     };
 
@@ -93,13 +107,18 @@ pub fn search(query: &str) -> Vec<AppEntry> {
         .collect()
 }
 
-/// Get details for a specific Flatpak app.
+/// Get details for a specific Flatpak app, from the local appstream
+/// data when offline or when Flathub can't be reached.
 pub fn get_details(app_id: &str) -> Option<AppEntry> {
+    if OFFLINE.load(Ordering::Relaxed) {
+        return appstream::get_details(app_id);
+    }
     let url = format!("https://flathub.org/api/v2/appstream/{}", app_id);
-    let resp = ureq::get(&url)
-        .timeout(Duration::from_secs(8))
-        .call()
-        .ok()?;
+    let resp = match ureq::get(&url).timeout(Duration::from_secs(8)).call() {
+        Ok(r) => r,
+        Err(ureq::Error::Status(404, _)) => return None,
+        Err(_) => return appstream::get_details(app_id),
+    };
 
     let detail: FlathubDetail = resp.into_json().ok()?;
     let installed = is_flatpak_installed(&detail.id);
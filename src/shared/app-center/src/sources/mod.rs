@@ -1,3 +1,5 @@
 pub mod appimage;
+pub mod appstream;
 pub mod aur;
 pub mod flathub;
+pub mod pacman;
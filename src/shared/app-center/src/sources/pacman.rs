@@ -0,0 +1,89 @@
+use crate::catalog::{AppEntry, Source};
+use std::process::{Command, Stdio};
+
+/// Search installed official-repo packages (`pacman -Qns`). They never
+/// show up in AUR results, but the security tracker covers them.
+pub fn search_installed(query: &str) -> Vec<AppEntry> {
+    if query.len() < 2 {
+        return Vec::new();
+    }
+    let Ok(out) = Command::new("pacman")
+        .args(["-Qns", "--", &regex_escape(query)])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    parse_search(&String::from_utf8_lossy(&out.stdout))
+}
+
+/// Parse `pacman -Qs` output: a `local/name version (groups)` line per
+/// package, followed by its indented description.
+fn parse_search(output: &str) -> Vec<AppEntry> {
+    let mut results: Vec<AppEntry> = Vec::new();
+    for line in output.lines() {
+        if let Some(description) = line.strip_prefix("    ") {
+            if let Some(entry) = results.last_mut() {
+                entry.description = description.trim().to_string();
+            }
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(version)) = (fields.next(), fields.next()) else { continue };
+        let name = name.rsplit('/').next().unwrap_or(name);
+        results.push(AppEntry {
+            name: name.to_string(),
+            id: name.to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            source: Source::Repo,
+            icon_url: String::new(),
+            icon_path: String::new(),
+            homepage: String::new(),
+            votes: 0,
+            popularity: 0.0,
+            installed: true,
+        });
+    }
+    results.truncate(50);
+    results
+}
+
+/// pacman matches search terms as extended regexes; match them literally.
+fn regex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_query_output() {
+        let output = "local/openssl 3.2.1-1\n    The Open Source toolkit for Secure Sockets Layer and Transport Layer Security\n\
+                      local/lib32-openssl 1:3.2.1-1\n    The Open Source toolkit for Secure Sockets Layer and Transport Layer Security (32-bit)\n\
+                      local/glibc 2.39+r52+gf8e4623421-1 (base)\n    GNU C Library\n";
+        let results = parse_search(output);
+        let names: Vec<_> = results.iter().map(|r| (r.id.as_str(), r.version.as_str())).collect();
+        assert_eq!(
+            names,
+            [("openssl", "3.2.1-1"), ("lib32-openssl", "1:3.2.1-1"), ("glibc", "2.39+r52+gf8e4623421-1")]
+        );
+        assert_eq!(results[2].description, "GNU C Library");
+        assert!(results.iter().all(|r| r.installed && r.source == Source::Repo));
+    }
+
+    #[test]
+    fn queries_match_literally() {
+        assert_eq!(regex_escape("c++"), "c\\+\\+");
+        assert_eq!(regex_escape("gtk3.0"), "gtk3\\.0");
+        assert_eq!(regex_escape("vim"), "vim");
+    }
+}
@@ -0,0 +1,162 @@
+use std::cmp::Ordering;
+
+/// Compare two package versions the way pacman's `vercmp` does
+/// (`[epoch:]pkgver[-pkgrel]`).
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, ver_a, rel_a) = parse_evr(a);
+    let (epoch_b, ver_b, rel_b) = parse_evr(b);
+
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(ver_a, ver_b))
+        .then_with(|| match (rel_a, rel_b) {
+            (Some(ra), Some(rb)) => rpmvercmp(ra, rb),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Split `epoch:version-release`. A missing epoch is "0".
+fn parse_evr(s: &str) -> (&str, &str, Option<&str>) {
+    let digits = s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    let (epoch, rest) = if s.as_bytes().get(digits) == Some(&b':') {
+        let epoch = &s[..digits];
+        (if epoch.is_empty() { "0" } else { epoch }, &s[digits + 1..])
+    } else {
+        ("0", s)
+    };
+    match rest.rfind('-') {
+        Some(i) => (epoch, &rest[..i], Some(&rest[i + 1..])),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Port of libalpm's `rpmvercmp`: compare alternating runs of digits and
+/// letters, treating any other characters as separators.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+    let (mut end1, mut end2) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+
+        // Different separator lengths decide the comparison.
+        if one - end1 != two - end2 {
+            return (one - end1).cmp(&(two - end2));
+        }
+
+        end1 = one;
+        end2 = two;
+        let is_num = a[end1].is_ascii_digit();
+        if is_num {
+            while end1 < a.len() && a[end1].is_ascii_digit() {
+                end1 += 1;
+            }
+            while end2 < b.len() && b[end2].is_ascii_digit() {
+                end2 += 1;
+            }
+        } else {
+            while end1 < a.len() && a[end1].is_ascii_alphabetic() {
+                end1 += 1;
+            }
+            while end2 < b.len() && b[end2].is_ascii_alphabetic() {
+                end2 += 1;
+            }
+        }
+
+        // The segments are of different types: numeric is newer.
+        if two == end2 {
+            return if is_num { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let mut seg1 = &a[one..end1];
+        let mut seg2 = &b[two..end2];
+        if is_num {
+            while seg1.len() > 1 && seg1[0] == b'0' {
+                seg1 = &seg1[1..];
+            }
+            while seg2.len() > 1 && seg2[0] == b'0' {
+                seg2 = &seg2[1..];
+            }
+            if seg1.len() != seg2.len() {
+                return seg1.len().cmp(&seg2.len());
+            }
+        }
+        match seg1.cmp(seg2) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        one = end1;
+        two = end2;
+    }
+
+    let rest1 = &a[one.min(a.len())..];
+    let rest2 = &b[two.min(b.len())..];
+    if rest1.is_empty() && rest2.is_empty() {
+        return Ordering::Equal;
+    }
+
+    // A remaining alpha string never beats an empty one ("1.0a" < "1.0").
+    let one_alpha = rest1.first().is_some_and(|c| c.is_ascii_alphabetic());
+    let two_alpha = rest2.first().is_some_and(|c| c.is_ascii_alphabetic());
+    if (rest1.is_empty() && !two_alpha) || one_alpha {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering::{Equal, Greater, Less};
+
+    #[test]
+    fn numeric_segments() {
+        assert_eq!(vercmp("1.0", "1.0"), Equal);
+        assert_eq!(vercmp("1.10", "1.9"), Greater);
+        assert_eq!(vercmp("1.0.1", "1.0"), Greater);
+        assert_eq!(vercmp("1.001", "1.1"), Equal);
+        assert_eq!(vercmp("1.0", "1_0"), Equal);
+    }
+
+    #[test]
+    fn alpha_segments() {
+        assert_eq!(vercmp("1.0a", "1.0"), Less);
+        assert_eq!(vercmp("1.0alpha", "1.0beta"), Less);
+        assert_eq!(vercmp("1.0rc1", "1.0"), Less);
+        assert_eq!(vercmp("1.0.a", "1.0.1"), Less);
+        assert_eq!(vercmp("1.0a", "1.0.1"), Less);
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(vercmp("1:1.0-1", "2.0-1"), Greater);
+        assert_eq!(vercmp("0:1.0-1", "1.0-1"), Equal);
+        assert_eq!(vercmp("1:1.0-1", "2:0.1-1"), Less);
+    }
+
+    #[test]
+    fn pkgrel() {
+        assert_eq!(vercmp("1.0-2", "1.0-1"), Greater);
+        assert_eq!(vercmp("1.0-1.1", "1.0-1"), Greater);
+        assert_eq!(vercmp("1.0-10", "1.0-9"), Greater);
+        // Without a pkgrel on one side only pkgver counts.
+        assert_eq!(vercmp("1.0", "1.0-5"), Equal);
+    }
+}
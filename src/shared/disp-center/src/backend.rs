@@ -1,4 +1,5 @@
-use crate::monitor::{Monitor, MonitorConfig};
+use crate::lid::{self, LidPolicy};
+use crate::monitor::{Monitor, MonitorConfig, WorkspaceRule};
 
 /// Trait for compositor-specific display management.
 /// Implement this for each compositor backend (Hyprland, DWM/xrandr, etc.).
@@ -9,18 +10,73 @@ pub trait DisplayBackend {
     /// Apply a set of monitor configurations live (without restarting the compositor).
     fn apply(&self, configs: &[MonitorConfig]) -> Result<(), String>;
 
-    /// Persist the monitor configuration to disk so it survives reboot.
-    fn persist(&self, configs: &[MonitorConfig]) -> Result<String, String>;
+    /// Persist the monitor configuration and workspace assignments to disk
+    /// so they survive reboot. Only the listed workspaces are changed; an
+    /// empty monitor removes the assignment.
+    fn persist(&self, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> Result<String, String>;
 
     /// Move workspace 1 (or the "default" workspace) to the given monitor,
     /// effectively making it the primary display.
     fn set_primary(&self, monitor_name: &str) -> Result<(), String>;
 
-    /// Flash a large label on each physical monitor so the user can identify which is which.
-    fn identify(&self, monitors: &[Monitor]) -> Result<(), String>;
-
     /// Human-readable name of this backend.
     fn name(&self) -> &'static str;
+
+    /// Whether `MonitorConfig::options` (VRR, bit depth, color management)
+    /// is honoured. The panel hides its "Advanced" section otherwise.
+    fn supports_advanced(&self) -> bool {
+        false
+    }
+
+    /// Whether workspaces can be assigned to monitors. The "Workspaces"
+    /// panel is hidden otherwise.
+    fn supports_workspaces(&self) -> bool {
+        false
+    }
+
+    /// Current workspace-to-monitor assignments.
+    fn workspace_rules(&self) -> Result<Vec<WorkspaceRule>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Assign workspaces live and move them to their monitors.
+    fn apply_workspaces(&self, _rules: &[WorkspaceRule]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether the compositor can turn the built-in display off when the
+    /// lid closes. "Turn off display" is only offered then.
+    fn supports_lid_switch(&self) -> bool {
+        false
+    }
+
+    /// What happens when the lid closes with an external monitor connected.
+    fn lid_policy(&self) -> LidPolicy {
+        lid::logind_policy()
+    }
+
+    /// Change what happens when the lid closes and save it. `internal` is
+    /// the built-in display's config, restored when the lid opens.
+    fn set_lid_policy(&self, policy: LidPolicy, _internal: &MonitorConfig) -> Result<(), String> {
+        if policy == LidPolicy::Disable {
+            return Err(format!("{} can't turn the display off when the lid closes", self.name()));
+        }
+        lid::set_logind_policy(policy)
+    }
+
+    /// Call `changed` from a background thread whenever outputs are
+    /// added, removed or reconfigured. Backends without an event stream
+    /// don't call it; the window's Refresh button still works there.
+    fn watch(&self, _changed: Box<dyn Fn() + Send>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Move the mapped window titled `title` to (`x`, `y`) in layout
+    /// coordinates and keep it above other windows. Wayland clients can't
+    /// place themselves; on X11 the window is moved directly instead.
+    fn place_overlay(&self, _title: &str, _x: i32, _y: i32) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 /// Detect the running compositor and return the appropriate backend.
@@ -30,14 +86,19 @@ pub fn detect_backend() -> Result<Box<dyn DisplayBackend>, String> {
         return Ok(Box::new(crate::hyprland::HyprlandBackend::new()));
     }
 
-    // Check for generic Wayland (future: sway, river, etc.)
+    // Sway (or any compositor exposing the i3 IPC socket)
+    if let Ok(socket) = std::env::var("SWAYSOCK") {
+        return Ok(Box::new(crate::sway::SwayBackend::new(socket)));
+    }
+
+    // Check for generic Wayland (future: river, etc.)
     if std::env::var("WAYLAND_DISPLAY").is_ok() {
-        return Err("Wayland compositor detected but not Hyprland. Only Hyprland is currently supported.".into());
+        return Err("Wayland compositor detected but not Hyprland or Sway. Only Hyprland and Sway are currently supported.".into());
     }
 
-    // X11 fallback (future: xrandr backend for DWM, i3, etc.)
+    // X11 (DWM, i3, etc.)
     if std::env::var("DISPLAY").is_ok() {
-        return Err("X11 detected. Only Hyprland is currently supported. X11/xrandr backend coming soon.".into());
+        return Ok(Box::new(crate::xrandr::XrandrBackend::new()));
     }
 
     Err("No display server detected.".into())
@@ -0,0 +1,269 @@
+use crate::backend::DisplayBackend;
+use crate::cvt;
+use crate::layout;
+use crate::monitor::{Monitor, MonitorConfig};
+use crate::profile::{self, Profile};
+
+pub const USAGE: &str = "\
+Usage: disp-center [--demo[=SCENARIO.json]] [--demo-log=FILE] <command>
+
+Commands:
+  list [--json]             Show connected monitors
+  set NAME [options]        Change one monitor and apply
+      --mode WxH[@HZ]       Resolution (highest refresh rate if @HZ is left out)
+      --cvt WxH@HZ          Custom mode from CVT timings (--cvt-rb: reduced blanking)
+      --pos XxY             Position in logical pixels
+      --scale S             Scale (must give whole logical pixels)
+      --transform N         Rotation: 0-3 = 0/90/180/270, 4-7 = flipped
+      --mirror NAME|none    Mirror another monitor, or stop mirroring
+      --enable, --disable   Turn the output on or off
+      --primary             Move workspace 1 to this monitor
+      --persist             Also write the compositor config
+  apply PROFILE [--persist] Apply a saved profile
+  save PROFILE              Save the current layout as a profile
+  help, --help              Show this help
+
+Without a command the settings window opens.";
+
+/// Commands handled without opening the window.
+pub fn is_command(arg: &str) -> bool {
+    matches!(arg, "list" | "set" | "apply" | "save")
+}
+
+/// Run a command line like `set DP-1 --scale 1.25` (without the program
+/// name and the `--demo` options).
+pub fn run(backend: &dyn DisplayBackend, args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    match command.as_str() {
+        "list" => list(backend, rest.iter().any(|a| a == "--json")),
+        "set" => set(backend, rest),
+        "apply" => apply_profile(backend, rest),
+        "save" => save_profile(backend, rest),
+        other => Err(format!("Unknown command '{other}'\n\n{USAGE}")),
+    }
+}
+
+fn list(backend: &dyn DisplayBackend, json: bool) -> Result<(), String> {
+    let monitors = backend.query_monitors()?;
+    if json {
+        let out = serde_json::to_string_pretty(&monitors)
+            .map_err(|e| format!("Failed to serialize monitors: {e}"))?;
+        println!("{out}");
+        return Ok(());
+    }
+
+    for m in &monitors {
+        if !m.enabled {
+            println!("{}  {}  disabled", m.name, m.description);
+            continue;
+        }
+        let mut line = format!(
+            "{}  {}  {}x{}@{:.2}  {}x{}  scale {}",
+            m.name, m.description, m.width, m.height, m.refresh_rate, m.x, m.y,
+            layout::format_scale(m.scale),
+        );
+        if m.transform != 0 {
+            line.push_str(&format!("  transform {}", m.transform));
+        }
+        if let Some(target) = &m.mirror {
+            line.push_str(&format!("  mirror of {target}"));
+        }
+        if m.focused {
+            line.push_str("  (focused)");
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn set(backend: &dyn DisplayBackend, args: &[String]) -> Result<(), String> {
+    let (name, opts) = args.split_first().ok_or("set: missing monitor name")?;
+    let monitors = backend.query_monitors()?;
+    let idx = monitors
+        .iter()
+        .position(|m| &m.name == name)
+        .ok_or_else(|| format!("No monitor named '{name}'"))?;
+
+    let mut updated = monitors.clone();
+    let mut primary = false;
+    let mut persist = false;
+    let mut opts = opts.iter();
+    while let Some(opt) = opts.next() {
+        let mut value = || opts.next().ok_or_else(|| format!("{opt} needs a value"));
+        let m = &mut updated[idx];
+        match opt.as_str() {
+            "--mode" => set_mode(m, value()?)?,
+            "--cvt" => set_custom_mode(m, opt, value()?, false)?,
+            "--cvt-rb" => set_custom_mode(m, opt, value()?, true)?,
+            "--pos" => (m.x, m.y) = parse_pair(value()?, 'x').ok_or("--pos: expected XxY")?,
+            "--scale" => {
+                m.scale = value()?.parse().map_err(|_| "--scale: expected a number")?;
+            }
+            "--transform" => {
+                m.transform = value()?
+                    .parse()
+                    .ok()
+                    .filter(|t| (0..8).contains(t))
+                    .ok_or("--transform: expected 0-7")?;
+            }
+            "--mirror" => {
+                let target = value()?;
+                m.mirror = if target == "none" { None } else { Some(target.clone()) };
+            }
+            "--enable" => m.enabled = true,
+            "--disable" => m.enabled = false,
+            "--primary" => primary = true,
+            "--persist" => persist = true,
+            other => return Err(format!("set: unknown option '{other}'")),
+        }
+    }
+
+    let m = &updated[idx];
+    if !layout::is_valid_scale(m.scale, m.width, m.height) {
+        return Err(format!(
+            "Scale {} doesn't give whole logical pixels at {}x{}; nearest valid scale is {}",
+            layout::format_scale(m.scale),
+            m.width,
+            m.height,
+            layout::format_scale(layout::nearest_valid_scale(m.scale, m.width, m.height)),
+        ));
+    }
+    if let Some(target) = &m.mirror {
+        if target == &m.name || !monitors.iter().any(|o| &o.name == target) {
+            return Err(format!("Can't mirror '{target}'"));
+        }
+    }
+
+    apply(backend, &updated, persist)?;
+    if primary {
+        backend.set_primary(name)?;
+    }
+    Ok(())
+}
+
+/// `WxH` or `WxH@HZ`, matched against the monitor's available modes.
+fn set_mode(m: &mut Monitor, spec: &str) -> Result<(), String> {
+    let (size, rate) = match spec.split_once('@') {
+        Some((size, rate)) => {
+            let rate: f64 = rate.parse().map_err(|_| format!("--mode: bad refresh rate in '{spec}'"))?;
+            (size, Some(rate))
+        }
+        None => (spec, None),
+    };
+    let (w, h) = parse_pair(size, 'x').ok_or_else(|| format!("--mode: expected WxH[@HZ], got '{spec}'"))?;
+
+    let mode = m
+        .available_modes
+        .iter()
+        .filter(|mode| mode.width == w && mode.height == h)
+        .filter(|mode| rate.is_none_or(|r| (mode.refresh_rate - r).abs() < 1.0))
+        .max_by(|a, b| a.refresh_rate.total_cmp(&b.refresh_rate))
+        .ok_or_else(|| {
+            let available: Vec<String> = m.available_modes.iter().map(|mode| mode.label()).collect();
+            format!("{} has no mode {spec}. Available: {}", m.name, available.join(", "))
+        })?;
+
+    m.width = mode.width;
+    m.height = mode.height;
+    m.refresh_rate = mode.refresh_rate;
+    m.custom_mode = None;
+    Ok(())
+}
+
+/// `WxH@HZ` for `--cvt`/`--cvt-rb`: generated timings instead of one of
+/// the modes the monitor lists.
+fn set_custom_mode(m: &mut Monitor, opt: &str, spec: &str, reduced: bool) -> Result<(), String> {
+    let expected = || format!("{opt}: expected WxH@HZ, got '{spec}'");
+    let (size, rate) = spec.split_once('@').ok_or_else(expected)?;
+    let (w, h) = parse_pair(size, 'x').ok_or_else(expected)?;
+    let rate: f64 = rate.parse().map_err(|_| expected())?;
+    let modeline = cvt::generate(w, h, rate, reduced).map_err(|e| format!("{opt}: {e}"))?;
+
+    let mode = modeline.mode();
+    m.width = mode.width;
+    m.height = mode.height;
+    m.refresh_rate = mode.refresh_rate;
+    m.custom_mode = Some(modeline);
+    Ok(())
+}
+
+/// "2560x1440" / "-1920x0" → (2560, 1440) / (-1920, 0).
+fn parse_pair(s: &str, sep: char) -> Option<(i32, i32)> {
+    let (a, b) = s.split_once(sep)?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+fn apply_profile(backend: &dyn DisplayBackend, args: &[String]) -> Result<(), String> {
+    let name = args.iter().find(|a| !a.starts_with("--")).ok_or("apply: missing profile name")?;
+    let persist = args.iter().any(|a| a == "--persist");
+
+    let monitors = backend.query_monitors()?;
+    let path = profile::profiles_path();
+    let profiles = profile::load(&path);
+    let profile = profiles
+        .iter()
+        .find(|p| &p.name == name)
+        .ok_or_else(|| format!("No profile named '{name}' in {}", path.display()))?;
+    if !profile.matches(&monitors) {
+        return Err(format!(
+            "Profile '{name}' is for {}, but {} is connected",
+            profile.outputs.iter().map(|o| o.description.as_str()).collect::<Vec<_>>().join(" + "),
+            Profile::default_name(&monitors),
+        ));
+    }
+
+    let (configs, primary) = profile.resolve(&monitors);
+    let workspaces = profile.resolve_workspaces(&monitors);
+    backend.apply(&configs)?;
+    backend.apply_workspaces(&workspaces)?;
+    if persist {
+        // Profiles saved without assignments keep the current ones.
+        let workspaces = if workspaces.is_empty() { backend.workspace_rules()? } else { workspaces };
+        let path = backend.persist(&configs, &workspaces)?;
+        println!("Saved to {path}");
+    }
+    if let Some(primary) = primary {
+        backend.set_primary(&primary)?;
+    }
+    println!("Applied profile '{name}'");
+    Ok(())
+}
+
+fn save_profile(backend: &dyn DisplayBackend, args: &[String]) -> Result<(), String> {
+    let name = args.first().ok_or("save: missing profile name")?;
+    let monitors = backend.query_monitors()?;
+    // Same notion of "primary" as the window: the focused monitor.
+    let primary = monitors
+        .iter()
+        .find(|m| m.focused)
+        .or(monitors.first())
+        .map(|m| m.name.clone())
+        .unwrap_or_default();
+
+    let workspaces = backend.workspace_rules()?;
+
+    let path = profile::profiles_path();
+    let mut profiles = profile::load(&path);
+    profile::upsert(&mut profiles, Profile::from_monitors(name, &monitors, &primary, &workspaces));
+    // upsert keeps an existing name; an explicit one wins here.
+    if let Some(p) = profiles.iter_mut().find(|p| p.matches(&monitors)) {
+        p.name = name.clone();
+    }
+    profile::save(&path, &profiles)?;
+    println!("Saved profile '{name}' for {}", Profile::default_name(&monitors));
+    Ok(())
+}
+
+/// Apply all monitors, warning about layout problems first.
+fn apply(backend: &dyn DisplayBackend, monitors: &[Monitor], persist: bool) -> Result<(), String> {
+    for problem in layout::problems(monitors) {
+        eprintln!("warning: {}", problem.message);
+    }
+    let configs: Vec<MonitorConfig> = monitors.iter().map(MonitorConfig::from_monitor).collect();
+    backend.apply(&configs)?;
+    if persist {
+        let path = backend.persist(&configs, &backend.workspace_rules()?)?;
+        println!("Saved to {path}");
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::monitor::MonitorMode;
+
+/// VESA Coordinated Video Timings 1.2, as computed by X.org's `cvt`.
+const H_GRANULARITY: i32 = 8;
+const MIN_V_PORCH: i32 = 3;
+const MIN_V_BPORCH: i32 = 6;
+const CLOCK_STEP_KHZ: i32 = 250;
+/// Minimum vertical sync + back porch time (µs).
+const MIN_VSYNC_BP: f64 = 550.0;
+const HSYNC_PERCENTAGE: i32 = 8;
+/// Blanking formula: C' and M' from the default M=600, C=40, K=128, J=20.
+const C_PRIME: f64 = 30.0;
+const M_PRIME: f64 = 300.0;
+
+/// Reduced blanking: minimum vertical blanking (µs), fixed horizontal
+/// sync and blanking (pixels), vertical front porch (lines).
+const RB_MIN_VBLANK: f64 = 460.0;
+const RB_H_SYNC: i32 = 32;
+const RB_H_BLANK: i32 = 160;
+const RB_V_FPORCH: i32 = 3;
+
+/// Generated timings for a custom mode, in the `Modeline` form X11,
+/// Hyprland and Sway accept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Modeline {
+    /// "2560x1080_75.00"; "R" after the size for reduced blanking.
+    pub name: String,
+    /// Pixel clock in kHz.
+    pub clock_khz: i32,
+    pub hdisplay: i32,
+    pub hsync_start: i32,
+    pub hsync_end: i32,
+    pub htotal: i32,
+    pub vdisplay: i32,
+    pub vsync_start: i32,
+    pub vsync_end: i32,
+    pub vtotal: i32,
+    /// Reduced blanking (CVT-RB): shorter blanking for digital displays,
+    /// lower pixel clock. Also decides the sync polarities.
+    pub reduced: bool,
+}
+
+impl Modeline {
+    /// The refresh rate the timings actually give (the clock is rounded).
+    pub fn refresh_rate(&self) -> f64 {
+        f64::from(self.clock_khz) * 1000.0 / f64::from(self.htotal * self.vtotal)
+    }
+
+    pub fn mode(&self) -> MonitorMode {
+        MonitorMode { width: self.hdisplay, height: self.vdisplay, refresh_rate: self.refresh_rate() }
+    }
+
+    /// Sync polarities: CVT uses -hsync +vsync, CVT-RB the reverse.
+    pub fn flags(&self) -> [&'static str; 2] {
+        if self.reduced {
+            ["+hsync", "-vsync"]
+        } else {
+            ["-hsync", "+vsync"]
+        }
+    }
+
+    /// "173.00 1920 2048 2248 2576 1080 1083 1088 1120 -hsync +vsync": the
+    /// modeline without its name, as Hyprland's and Sway's `modeline` and
+    /// `xrandr --newmode` take it.
+    pub fn timings(&self) -> String {
+        let [h, v] = self.flags();
+        format!(
+            "{:.2} {} {} {} {} {} {} {} {} {h} {v}",
+            f64::from(self.clock_khz) / 1000.0,
+            self.hdisplay, self.hsync_start, self.hsync_end, self.htotal,
+            self.vdisplay, self.vsync_start, self.vsync_end, self.vtotal,
+        )
+    }
+}
+
+impl fmt::Display for Modeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Modeline \"{}\" {}", self.name, self.timings())
+    }
+}
+
+/// CVT (or with `reduced`, CVT-RB) timings for `width`x`height` at
+/// `refresh` Hz. The width is rounded down to a multiple of 8.
+pub fn generate(width: i32, height: i32, refresh: f64, reduced: bool) -> Result<Modeline, String> {
+    if !(320..=16384).contains(&width) || !(200..=16384).contains(&height) {
+        return Err(format!("{width}x{height} is outside 320x200 to 16384x16384"));
+    }
+    if !(10.0..=1000.0).contains(&refresh) {
+        return Err(format!("{refresh} Hz is outside 10 to 1000 Hz"));
+    }
+    let hdisplay = width - width % H_GRANULARITY;
+    let vdisplay = height;
+    // The aspect ratio is taken from the requested width, as libxcvt does.
+    let vsync = vsync_width(width, vdisplay);
+
+    let (hperiod, htotal, hsync_start, hsync_end, vtotal, vsync_start) = if reduced {
+        // Horizontal period (µs) estimated from the fixed blanking time.
+        let hperiod = (1_000_000.0 / refresh - RB_MIN_VBLANK) / f64::from(vdisplay);
+        let vbi_lines = ((RB_MIN_VBLANK / hperiod) as i32 + 1).max(RB_V_FPORCH + vsync + MIN_V_BPORCH);
+        let hsync_end = hdisplay + RB_H_BLANK / 2;
+        (
+            hperiod,
+            hdisplay + RB_H_BLANK,
+            hsync_end - RB_H_SYNC,
+            hsync_end,
+            vdisplay + vbi_lines,
+            vdisplay + RB_V_FPORCH,
+        )
+    } else {
+        let hperiod = (1_000_000.0 / refresh - MIN_VSYNC_BP) / f64::from(vdisplay + MIN_V_PORCH);
+        let vsync_bp = ((MIN_VSYNC_BP / hperiod) as i32 + 1).max(vsync + MIN_V_PORCH);
+        let vtotal = vdisplay + vsync_bp + MIN_V_PORCH;
+
+        // Ideal blanking duty cycle, at least 20%.
+        let blank_percent = (C_PRIME - M_PRIME * hperiod / 1000.0).max(20.0);
+        let mut hblank = (f64::from(hdisplay) * blank_percent / (100.0 - blank_percent)) as i32;
+        hblank -= hblank % (2 * H_GRANULARITY);
+        let htotal = hdisplay + hblank;
+
+        let hsync_end = hdisplay + hblank / 2;
+        let mut hsync_start = hsync_end - htotal * HSYNC_PERCENTAGE / 100;
+        hsync_start += H_GRANULARITY - hsync_start % H_GRANULARITY;
+        (hperiod, htotal, hsync_start, hsync_end, vtotal, vdisplay + MIN_V_PORCH)
+    };
+    if hperiod <= 0.0 {
+        return Err(format!("{refresh} Hz is too fast for {width}x{height}"));
+    }
+
+    let mut clock_khz = (f64::from(htotal) * 1000.0 / hperiod) as i32;
+    clock_khz -= clock_khz % CLOCK_STEP_KHZ;
+
+    Ok(Modeline {
+        name: format!("{hdisplay}x{vdisplay}{}_{refresh:.2}", if reduced { "R" } else { "" }),
+        clock_khz,
+        hdisplay,
+        hsync_start,
+        hsync_end,
+        htotal,
+        vdisplay,
+        vsync_start,
+        vsync_end: vsync_start + vsync,
+        vtotal,
+        reduced,
+    })
+}
+
+/// Vertical sync width, which encodes the aspect ratio in CVT.
+fn vsync_width(width: i32, height: i32) -> i32 {
+    let is = |w: i32, h: i32| height % h == 0 && height * w / h == width;
+    if is(4, 3) {
+        4
+    } else if is(16, 9) {
+        5
+    } else if is(16, 10) {
+        6
+    } else if is(5, 4) || is(15, 9) {
+        7
+    } else {
+        10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `name` and timings as libxcvt's `cvt` prints them.
+    fn cvt(width: i32, height: i32, refresh: f64, reduced: bool) -> (String, String) {
+        let modeline = generate(width, height, refresh, reduced).unwrap();
+        (modeline.name.clone(), modeline.timings())
+    }
+
+    #[test]
+    fn matches_cvt() {
+        assert_eq!(
+            cvt(1920, 1080, 60.0, false),
+            ("1920x1080_60.00".into(), "173.00 1920 2048 2248 2576 1080 1083 1088 1120 -hsync +vsync".into())
+        );
+        assert_eq!(
+            cvt(2560, 1440, 60.0, false).1,
+            "312.25 2560 2752 3024 3488 1440 1443 1448 1493 -hsync +vsync"
+        );
+        assert_eq!(cvt(800, 600, 60.0, false).1, "38.25 800 832 912 1024 600 603 607 624 -hsync +vsync");
+        assert_eq!(
+            cvt(3840, 2160, 30.0, false).1,
+            "338.75 3840 4080 4488 5136 2160 2163 2168 2200 -hsync +vsync"
+        );
+    }
+
+    #[test]
+    fn matches_cvt_reduced() {
+        assert_eq!(
+            cvt(1920, 1080, 60.0, true),
+            ("1920x1080R_60.00".into(), "138.50 1920 1968 2000 2080 1080 1083 1088 1111 +hsync -vsync".into())
+        );
+        assert_eq!(
+            cvt(2560, 1440, 60.0, true).1,
+            "241.50 2560 2608 2640 2720 1440 1443 1448 1481 +hsync -vsync"
+        );
+        assert_eq!(
+            cvt(2560, 1440, 120.0, true).1,
+            "497.25 2560 2608 2640 2720 1440 1443 1448 1525 +hsync -vsync"
+        );
+    }
+
+    #[test]
+    fn vsync_follows_requested_width() {
+        // 1404x1053 is 4:3, but only before rounding the width to 1400.
+        let modeline = generate(1404, 1053, 60.0, false).unwrap();
+        assert_eq!(modeline.hdisplay, 1400);
+        assert_eq!(modeline.vsync_end - modeline.vsync_start, 4);
+        // 1366x768 isn't quite 16:9.
+        let modeline = generate(1366, 768, 60.0, false).unwrap();
+        assert_eq!((modeline.hdisplay, modeline.vsync_end - modeline.vsync_start), (1360, 10));
+    }
+
+    #[test]
+    fn refresh_rate_from_timings() {
+        let modeline = generate(1920, 1080, 60.0, false).unwrap();
+        assert!((modeline.refresh_rate() - 59.96).abs() < 0.01);
+        assert_eq!(modeline.mode().width, 1920);
+        assert_eq!(
+            modeline.to_string(),
+            "Modeline \"1920x1080_60.00\" 173.00 1920 2048 2248 2576 1080 1083 1088 1120 -hsync +vsync"
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range() {
+        assert!(generate(100, 100, 60.0, false).is_err());
+        assert!(generate(1920, 1080, 5.0, false).is_err());
+        assert!(generate(1920, 1080, 2000.0, true).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use crate::backend::DisplayBackend;
+use crate::profile;
+
+/// Wait this long after the last hotplug event before applying, so a dock
+/// bringing up several outputs at once triggers a single apply.
+const SETTLE: Duration = Duration::from_millis(700);
+
+/// `disp-center --daemon`: apply the matching profile at startup and
+/// whenever Hyprland reports a monitor being added or removed.
+pub fn run(backend: &dyn DisplayBackend) -> Result<(), String> {
+    let path = crate::hyprland::event_socket_path()?;
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| format!("Failed to connect to {}: {e}", path.display()))?;
+    eprintln!("disp-center daemon: listening on {}", path.display());
+
+    apply_matching(backend);
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    let mut pending = false;
+    loop {
+        match reader.read_line(&mut line) {
+            Ok(0) => return Err("Hyprland closed the event socket".into()),
+            Ok(_) => {
+                if crate::hyprland::is_hotplug_event(&line) {
+                    pending = true;
+                    let _ = reader.get_ref().set_read_timeout(Some(SETTLE));
+                }
+                line.clear();
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if pending {
+                    pending = false;
+                    let _ = reader.get_ref().set_read_timeout(None);
+                    apply_matching(backend);
+                }
+            }
+            Err(e) => return Err(format!("Failed to read Hyprland events: {e}")),
+        }
+    }
+}
+
+/// Apply the saved profile for the connected monitor set, if there is one.
+fn apply_matching(backend: &dyn DisplayBackend) {
+    let monitors = match backend.query_monitors() {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("disp-center daemon: {e}");
+            return;
+        }
+    };
+    let profiles = profile::load(&profile::profiles_path());
+    let Some(profile) = profile::find_matching(&profiles, &monitors) else {
+        eprintln!("disp-center daemon: no profile for {}", profile::Profile::default_name(&monitors));
+        return;
+    };
+
+    let (configs, primary) = profile.resolve(&monitors);
+    if let Err(e) = backend.apply(&configs) {
+        eprintln!("disp-center daemon: applying '{}' failed: {e}", profile.name);
+        return;
+    }
+    if let Err(e) = backend.apply_workspaces(&profile.resolve_workspaces(&monitors)) {
+        eprintln!("disp-center daemon: moving workspaces for '{}' failed: {e}", profile.name);
+    }
+    if let Some(name) = primary {
+        let _ = backend.set_primary(&name);
+    }
+    eprintln!("disp-center daemon: applied profile '{}'", profile.name);
+}
@@ -1,81 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
 use crate::backend::DisplayBackend;
-use crate::monitor::{Monitor, MonitorConfig, MonitorMode};
+use crate::edid::Edid;
+use crate::lid::LidPolicy;
+use crate::monitor::{Monitor, MonitorConfig, MonitorMode, OutputOptions, WorkspaceRule};
 
 /// A demo backend with fake monitors for UI testing on any display server.
-pub struct DemoBackend;
+/// `--demo=FILE` loads the monitors from a scenario file; `--demo-log=FILE`
+/// records every call that would change the displays.
+pub struct DemoBackend {
+    scenario: Scenario,
+    /// Current state: `apply` and friends change it like a compositor would.
+    monitors: RefCell<Vec<Monitor>>,
+    workspaces: RefCell<Vec<WorkspaceRule>>,
+    lid_policy: RefCell<LidPolicy>,
+    log: Option<PathBuf>,
+}
+
+/// A scenario file: the monitors as printed by `list --json`, either as a
+/// bare array or with workspaces, capabilities and injected failures:
+///
+/// ```json
+/// { "monitors": [...], "workspaces": [{"workspace": 1, "monitor": "DP-1"}],
+///   "fail": {"apply": "simulated failure"} }
+/// ```
+#[derive(Deserialize)]
+#[serde(default)]
+struct Scenario {
+    monitors: Vec<Monitor>,
+    /// Only the built-in scenario has workspaces by default.
+    #[serde(default)]
+    workspaces: Vec<WorkspaceRule>,
+    supports_advanced: bool,
+    supports_workspaces: bool,
+    supports_lid_switch: bool,
+    lid_policy: LidPolicy,
+    fail: Failures,
+}
+
+/// Calls that fail with the given message (after being logged).
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Failures {
+    query: Option<String>,
+    apply: Option<String>,
+    persist: Option<String>,
+    set_primary: Option<String>,
+    apply_workspaces: Option<String>,
+    set_lid_policy: Option<String>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            monitors: default_monitors(),
+            workspaces: [(1, "DP-1"), (2, "DP-1"), (3, "HDMI-A-1")]
+                .into_iter()
+                .map(|(workspace, monitor)| WorkspaceRule { workspace, monitor: monitor.into() })
+                .collect(),
+            supports_advanced: true,
+            supports_workspaces: true,
+            supports_lid_switch: true,
+            lid_policy: LidPolicy::Ignore,
+            fail: Failures::default(),
+        }
+    }
+}
+
+/// One line of the call log.
+#[derive(Serialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+enum Call<'a> {
+    Apply { configs: &'a [MonitorConfig] },
+    Persist { configs: &'a [MonitorConfig], workspaces: &'a [WorkspaceRule] },
+    SetPrimary { monitor: &'a str },
+    ApplyWorkspaces { rules: &'a [WorkspaceRule] },
+    SetLidPolicy { policy: LidPolicy, internal: &'a MonitorConfig },
+}
+
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    #[serde(flatten)]
+    call: Call<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
 
 impl DemoBackend {
     pub fn new() -> Self {
-        Self
+        Self::with_scenario(Scenario::default())
+    }
+
+    /// Load a scenario file (see `Scenario`).
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        let mut scenario = if value.is_array() {
+            let monitors = serde_json::from_value(value)
+                .map_err(|e| format!("Bad monitor list in {}: {e}", path.display()))?;
+            Scenario { monitors, workspaces: Vec::new(), ..Scenario::default() }
+        } else {
+            serde_json::from_value(value).map_err(|e| format!("Bad scenario in {}: {e}", path.display()))?
+        };
+        for m in scenario.monitors.iter_mut().filter(|m| m.description.is_empty()) {
+            m.description = m.name.clone();
+        }
+        Ok(Self::with_scenario(scenario))
+    }
+
+    fn with_scenario(scenario: Scenario) -> Self {
+        Self {
+            monitors: RefCell::new(scenario.monitors.clone()),
+            workspaces: RefCell::new(scenario.workspaces.clone()),
+            lid_policy: RefCell::new(scenario.lid_policy),
+            scenario,
+            log: None,
+        }
+    }
+
+    /// Append every call to `path` as one JSON object per line. The file is
+    /// truncated first so each run starts with an empty log.
+    pub fn log_to(mut self, path: PathBuf) -> Result<Self, String> {
+        std::fs::write(&path, "").map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+        self.log = Some(path);
+        Ok(self)
+    }
+
+    /// Log `call`, then fail with `failure` if the scenario injects one.
+    fn record(&self, call: Call, failure: &Option<String>) -> Result<(), String> {
+        if let Some(path) = &self.log {
+            let entry = LogEntry { call, error: failure.as_deref() };
+            let line = serde_json::to_string(&entry).unwrap_or_default();
+            let written = std::fs::OpenOptions::new()
+                .append(true)
+                .open(path)
+                .and_then(|mut f| writeln!(f, "{line}"));
+            if let Err(e) = written {
+                eprintln!("[demo] Failed to write {}: {e}", path.display());
+            }
+        }
+        match failure {
+            Some(message) => Err(message.clone()),
+            None => Ok(()),
+        }
     }
 }
 
+/// What a real EDID would report for the demo monitors.
+fn demo_edid(manufacturer: &str, name: Option<&str>, size_mm: (i32, i32), preferred: MonitorMode) -> Option<Edid> {
+    Some(Edid {
+        manufacturer: manufacturer.into(),
+        product_code: 0x4171,
+        serial: Some("DEMO0001".into()),
+        name: name.map(str::to_string),
+        width_mm: size_mm.0,
+        height_mm: size_mm.1,
+        preferred: Some(preferred),
+    })
+}
+
+/// The built-in scenario: a 4K desktop monitor, a 1080p monitor to its
+/// right and a laptop panel below.
+fn default_monitors() -> Vec<Monitor> {
+    vec![
+        Monitor {
+            id: 0,
+            name: "DP-1".into(),
+            description: "Demo 27\" 4K Monitor".into(),
+            width: 3840,
+            height: 2160,
+            refresh_rate: 144.0,
+            x: 0,
+            y: 0,
+            scale: 1.5,
+            transform: 0,
+            enabled: true,
+            dpms: true,
+            focused: true,
+            mirror: None,
+            options: OutputOptions::default(),
+            available_modes: vec![
+                MonitorMode { width: 3840, height: 2160, refresh_rate: 144.0 },
+                MonitorMode { width: 3840, height: 2160, refresh_rate: 60.0 },
+                MonitorMode { width: 2560, height: 1440, refresh_rate: 165.0 },
+                MonitorMode { width: 2560, height: 1440, refresh_rate: 60.0 },
+                MonitorMode { width: 1920, height: 1080, refresh_rate: 60.0 },
+            ],
+            custom_mode: None,
+            edid: demo_edid(
+                "DEL",
+                Some("DELL U2720Q"),
+                (597, 336),
+                MonitorMode { width: 3840, height: 2160, refresh_rate: 60.0 },
+            ),
+        },
+        Monitor {
+            id: 1,
+            name: "HDMI-A-1".into(),
+            description: "Demo 24\" 1080p Monitor".into(),
+            width: 1920,
+            height: 1080,
+            refresh_rate: 60.0,
+            x: 2560,
+            y: 0,
+            scale: 1.0,
+            transform: 0,
+            enabled: true,
+            dpms: true,
+            focused: false,
+            mirror: None,
+            options: OutputOptions::default(),
+            available_modes: vec![
+                MonitorMode { width: 1920, height: 1080, refresh_rate: 144.0 },
+                MonitorMode { width: 1920, height: 1080, refresh_rate: 60.0 },
+                MonitorMode { width: 1280, height: 720, refresh_rate: 60.0 },
+            ],
+            custom_mode: None,
+            edid: demo_edid(
+                "GSM",
+                Some("LG 24MK430H"),
+                (527, 296),
+                MonitorMode { width: 1920, height: 1080, refresh_rate: 60.0 },
+            ),
+        },
+        Monitor {
+            id: 2,
+            name: "eDP-1".into(),
+            description: "Demo 14\" Laptop Display".into(),
+            width: 2880,
+            height: 1800,
+            refresh_rate: 120.0,
+            x: 0,
+            y: 1440,
+            scale: 2.0,
+            transform: 0,
+            enabled: true,
+            dpms: true,
+            focused: false,
+            mirror: None,
+            options: OutputOptions::default(),
+            available_modes: vec![
+                MonitorMode { width: 2880, height: 1800, refresh_rate: 120.0 },
+                MonitorMode { width: 2880, height: 1800, refresh_rate: 60.0 },
+                MonitorMode { width: 1920, height: 1200, refresh_rate: 60.0 },
+            ],
+            custom_mode: None,
+            edid: demo_edid(
+                "SDC",
+                None,
+                (302, 189),
+                MonitorMode { width: 2880, height: 1800, refresh_rate: 120.0 },
+            ),
+        },
+    ]
+}
+
 impl DisplayBackend for DemoBackend {
     fn query_monitors(&self) -> Result<Vec<Monitor>, String> {
-        Ok(vec![
-            Monitor {
-                id: 0,
-                name: "DP-1".into(),
-                description: "Demo 27\" 4K Monitor".into(),
-                width: 3840,
-                height: 2160,
-                refresh_rate: 144.0,
-                x: 0,
-                y: 0,
-                scale: 1.5,
-                transform: 0,
-                enabled: true,
-                dpms: true,
-                focused: true,
-                available_modes: vec![
-                    MonitorMode { width: 3840, height: 2160, refresh_rate: 144.0 },
-                    MonitorMode { width: 3840, height: 2160, refresh_rate: 60.0 },
-                    MonitorMode { width: 2560, height: 1440, refresh_rate: 165.0 },
-                    MonitorMode { width: 2560, height: 1440, refresh_rate: 60.0 },
-                    MonitorMode { width: 1920, height: 1080, refresh_rate: 60.0 },
-                ],
-            },
-            Monitor {
-                id: 1,
-                name: "HDMI-A-1".into(),
-                description: "Demo 24\" 1080p Monitor".into(),
-                width: 1920,
-                height: 1080,
-                refresh_rate: 60.0,
-                x: 3840,
-                y: 0,
-                scale: 1.0,
-                transform: 0,
-                enabled: true,
-                dpms: true,
-                focused: false,
-                available_modes: vec![
-                    MonitorMode { width: 1920, height: 1080, refresh_rate: 144.0 },
-                    MonitorMode { width: 1920, height: 1080, refresh_rate: 60.0 },
-                    MonitorMode { width: 1280, height: 720, refresh_rate: 60.0 },
-                ],
-            },
-            Monitor {
-                id: 2,
-                name: "eDP-1".into(),
-                description: "Demo 14\" Laptop Display".into(),
-                width: 2880,
-                height: 1800,
-                refresh_rate: 120.0,
-                x: 0,
-                y: 2160,
-                scale: 2.0,
-                transform: 0,
-                enabled: true,
-                dpms: true,
-                focused: false,
-                available_modes: vec![
-                    MonitorMode { width: 2880, height: 1800, refresh_rate: 120.0 },
-                    MonitorMode { width: 2880, height: 1800, refresh_rate: 60.0 },
-                    MonitorMode { width: 1920, height: 1200, refresh_rate: 60.0 },
-                ],
-            },
-        ])
+        if let Some(message) = &self.scenario.fail.query {
+            return Err(message.clone());
+        }
+        Ok(self.monitors.borrow().clone())
     }
 
     fn apply(&self, configs: &[MonitorConfig]) -> Result<(), String> {
@@ -83,28 +273,89 @@ impl DisplayBackend for DemoBackend {
         for c in configs {
             eprintln!("  {}", c.to_hyprland_line());
         }
+        self.record(Call::Apply { configs }, &self.scenario.fail.apply)?;
+        for c in configs {
+            if let Some(m) = self.monitors.borrow_mut().iter_mut().find(|m| m.name == c.name) {
+                m.width = c.width;
+                m.height = c.height;
+                m.refresh_rate = c.refresh_rate;
+                m.x = c.x;
+                m.y = c.y;
+                m.scale = c.scale;
+                m.transform = c.transform;
+                m.mirror = c.mirror.clone();
+                m.options = c.options.clone();
+                m.enabled = c.enabled;
+                m.custom_mode = c.modeline.clone();
+            }
+        }
         Ok(())
     }
 
-    fn persist(&self, configs: &[MonitorConfig]) -> Result<String, String> {
-        eprintln!("[demo] Would persist {} monitor config(s)", configs.len());
+    fn persist(&self, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> Result<String, String> {
+        eprintln!(
+            "[demo] Would persist {} monitor config(s) and {} workspace rule(s)",
+            configs.len(),
+            workspaces.len()
+        );
+        self.record(Call::Persist { configs, workspaces }, &self.scenario.fail.persist)?;
         Ok("(demo mode - not saved)".into())
     }
 
     fn set_primary(&self, monitor_name: &str) -> Result<(), String> {
         eprintln!("[demo] Would set primary: {monitor_name}");
+        self.record(Call::SetPrimary { monitor: monitor_name }, &self.scenario.fail.set_primary)?;
+        let mut workspaces = self.workspaces.borrow_mut();
+        workspaces.retain(|w| w.workspace != 1);
+        workspaces.insert(0, WorkspaceRule { workspace: 1, monitor: monitor_name.into() });
         Ok(())
     }
 
-    fn identify(&self, monitors: &[crate::monitor::Monitor]) -> Result<(), String> {
-        eprintln!("[demo] Identifying {} monitors:", monitors.len());
-        for (i, m) in monitors.iter().enumerate() {
-            eprintln!("  [{}] {} - {}x{}", i + 1, m.name, m.width, m.height);
+    fn name(&self) -> &'static str {
+        "Demo"
+    }
+
+    fn supports_advanced(&self) -> bool {
+        self.scenario.supports_advanced
+    }
+
+    fn supports_workspaces(&self) -> bool {
+        self.scenario.supports_workspaces
+    }
+
+    fn workspace_rules(&self) -> Result<Vec<WorkspaceRule>, String> {
+        Ok(self.workspaces.borrow().clone())
+    }
+
+    fn apply_workspaces(&self, rules: &[WorkspaceRule]) -> Result<(), String> {
+        for r in rules {
+            eprintln!("  workspace = {}, monitor:{}, default:true", r.workspace, r.monitor);
         }
+        self.record(Call::ApplyWorkspaces { rules }, &self.scenario.fail.apply_workspaces)?;
+        let mut workspaces = self.workspaces.borrow_mut();
+        workspaces.retain(|w| !rules.iter().any(|r| r.workspace == w.workspace));
+        workspaces.extend(rules.iter().cloned());
+        workspaces.sort_by_key(|w| w.workspace);
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
-        "Demo"
+    fn supports_lid_switch(&self) -> bool {
+        self.scenario.supports_lid_switch
+    }
+
+    fn lid_policy(&self) -> LidPolicy {
+        *self.lid_policy.borrow()
+    }
+
+    fn set_lid_policy(&self, policy: LidPolicy, internal: &MonitorConfig) -> Result<(), String> {
+        eprintln!("[demo] Would set lid policy for {}: {}", internal.name, policy.label());
+        self.record(Call::SetLidPolicy { policy, internal }, &self.scenario.fail.set_lid_policy)?;
+        *self.lid_policy.borrow_mut() = policy;
+        Ok(())
+    }
+
+    fn place_overlay(&self, title: &str, x: i32, y: i32) -> Result<(), String> {
+        eprintln!("[demo] Would place '{title}' at {x},{y}");
+        Ok(())
     }
 }
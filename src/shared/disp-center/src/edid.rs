@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+
+use crate::layout;
+use crate::monitor::MonitorMode;
+
+const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Logical DPI the recommended scale aims for. Built-in panels are viewed
+/// from closer, so they get away with smaller text.
+const DESKTOP_DPI: f64 = 110.0;
+const BUILTIN_DPI: f64 = 130.0;
+
+/// PNP IDs of common monitor and panel makers.
+const VENDORS: &[(&str, &str)] = &[
+    ("ACR", "Acer"),
+    ("AOC", "AOC"),
+    ("APP", "Apple"),
+    ("AUO", "AU Optronics"),
+    ("AUS", "ASUS"),
+    ("BNQ", "BenQ"),
+    ("BOE", "BOE"),
+    ("CMN", "Chimei Innolux"),
+    ("DEL", "Dell"),
+    ("ENC", "EIZO"),
+    ("GBT", "Gigabyte"),
+    ("GSM", "LG"),
+    ("HPN", "HP"),
+    ("HWP", "HP"),
+    ("IVM", "iiyama"),
+    ("LEN", "Lenovo"),
+    ("LGD", "LG Display"),
+    ("MSI", "MSI"),
+    ("NEC", "NEC"),
+    ("PHL", "Philips"),
+    ("SAM", "Samsung"),
+    ("SDC", "Samsung Display"),
+    ("SHP", "Sharp"),
+    ("SNY", "Sony"),
+    ("VSC", "ViewSonic"),
+];
+
+/// What the monitor reports about itself in its EDID base block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Edid {
+    /// Three-letter PNP manufacturer ID, e.g. "DEL".
+    pub manufacturer: String,
+    pub product_code: u16,
+    /// Serial number descriptor, or the numeric serial if there is none.
+    pub serial: Option<String>,
+    /// Monitor name descriptor, e.g. "DELL U2720Q".
+    pub name: Option<String>,
+    /// Physical image size; 0 for projectors and displays that don't say.
+    pub width_mm: i32,
+    pub height_mm: i32,
+    /// Native mode from the first detailed timing.
+    pub preferred: Option<MonitorMode>,
+}
+
+impl Edid {
+    /// "Dell U2720Q": the vendor's name, then the monitor name without the
+    /// vendor repeated in it (or the product code). Unknown vendors are
+    /// left to the monitor name.
+    pub fn model(&self) -> String {
+        let vendor = VENDORS.iter().find(|(id, _)| *id == self.manufacturer).map(|(_, name)| *name);
+        match (vendor, &self.name) {
+            (Some(vendor), Some(name)) => {
+                let first = name.split_whitespace().next().unwrap_or_default();
+                let rest = if first.eq_ignore_ascii_case(vendor) { name[first.len()..].trim() } else { name };
+                format!("{vendor} {rest}").trim().to_string()
+            }
+            (None, Some(name)) => name.clone(),
+            (vendor, None) => {
+                format!("{} {:04X}", vendor.unwrap_or(&self.manufacturer), self.product_code)
+            }
+        }
+    }
+
+    /// Screen diagonal in inches.
+    pub fn diagonal_inches(&self) -> Option<f64> {
+        self.has_size()
+            .then(|| f64::from(self.width_mm).hypot(f64::from(self.height_mm)) / 25.4)
+    }
+
+    /// Pixels per inch at a horizontal resolution of `width` (the mode's
+    /// width before rotation, like the physical size).
+    pub fn dpi(&self, width: i32) -> Option<f64> {
+        (self.has_size() && width > 0).then(|| f64::from(width) / (f64::from(self.width_mm) / 25.4))
+    }
+
+    /// A valid scale that brings `width`x`height` close to a comfortable
+    /// logical DPI. `builtin` is for laptop panels (see `is_builtin`).
+    pub fn recommended_scale(&self, width: i32, height: i32, builtin: bool) -> Option<f64> {
+        let target = if builtin { BUILTIN_DPI } else { DESKTOP_DPI };
+        let scale = self.dpi(width)? / target;
+        // Quarter steps read best; snap to one the mode can divide.
+        let quarter = ((scale * 4.0).round() / 4.0).clamp(1.0, 3.0);
+        Some(layout::nearest_valid_scale(quarter, width, height))
+    }
+
+    /// Whether the size is real. Some displays leave it out or (EDID 1.4)
+    /// store an aspect ratio in its place; a few report absurd values.
+    fn has_size(&self) -> bool {
+        self.width_mm >= 100 && self.height_mm >= 50 && self.width_mm <= 3000 && self.height_mm <= 2000
+    }
+}
+
+/// Connectors of built-in panels.
+pub fn is_builtin(connector: &str) -> bool {
+    ["eDP", "LVDS", "DSI"].iter().any(|p| connector.starts_with(p))
+}
+
+/// Parse an EDID base block (extension blocks are ignored).
+pub fn parse(bytes: &[u8]) -> Result<Edid, String> {
+    if bytes.len() < 128 {
+        return Err(format!("EDID too short ({} bytes)", bytes.len()));
+    }
+    if bytes[..8] != HEADER {
+        return Err("Not an EDID (bad header)".into());
+    }
+
+    let id = u16::from_be_bytes([bytes[8], bytes[9]]);
+    let letter = |shift: u16| (((id >> shift) & 0x1f) as u8 + b'A' - 1) as char;
+    let manufacturer: String = [letter(10), letter(5), letter(0)].iter().collect();
+    let product_code = u16::from_le_bytes([bytes[10], bytes[11]]);
+    let serial_number = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+
+    // Size in cm, refined below by the detailed timing's size in mm.
+    let (mut width_mm, mut height_mm) = (i32::from(bytes[21]) * 10, i32::from(bytes[22]) * 10);
+
+    let mut name = None;
+    let mut serial = None;
+    let mut preferred = None;
+    for d in bytes[54..126].chunks_exact(18) {
+        if d[0] != 0 || d[1] != 0 {
+            // Detailed timing; the first one is the preferred mode.
+            if preferred.is_none() {
+                let (mode, size) = detailed_timing(d);
+                preferred = mode;
+                if size.0 > 0 && size.1 > 0 {
+                    (width_mm, height_mm) = size;
+                }
+            }
+            continue;
+        }
+        match d[3] {
+            0xfc => name = descriptor_text(d),
+            0xff => serial = descriptor_text(d),
+            _ => {}
+        }
+    }
+
+    Ok(Edid {
+        manufacturer,
+        product_code,
+        serial: serial.or_else(|| (serial_number != 0).then(|| serial_number.to_string())),
+        name,
+        width_mm,
+        height_mm,
+        preferred,
+    })
+}
+
+/// Parse the hex dump xrandr prints under "EDID:".
+pub fn from_hex(hex: &str) -> Option<Edid> {
+    let bytes: Vec<u8> = (0..hex.len() / 2)
+        .filter_map(|i| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok())
+        .collect();
+    parse(&bytes).ok()
+}
+
+/// EDID of a DRM connector ("DP-1") from `/sys/class/drm/card*-DP-1/edid`.
+/// These are the names Hyprland and Sway use.
+pub fn for_connector(connector: &str) -> Option<Edid> {
+    std::fs::read_dir("/sys/class/drm")
+        .ok()?
+        .flatten()
+        .filter(|e| {
+            let file = e.file_name();
+            let file = file.to_string_lossy();
+            // "card1-DP-1" → "DP-1"
+            file.starts_with("card") && file.split_once('-').is_some_and(|(_, c)| c == connector)
+        })
+        .find_map(|e| parse(&std::fs::read(e.path().join("edid")).ok()?).ok())
+}
+
+/// Mode and image size (mm) of an 18-byte detailed timing descriptor.
+fn detailed_timing(d: &[u8]) -> (Option<MonitorMode>, (i32, i32)) {
+    let clock = f64::from(u16::from_le_bytes([d[0], d[1]])) * 10_000.0;
+    let h_active = i32::from(d[2]) | (i32::from(d[4] & 0xf0) << 4);
+    let h_blank = i32::from(d[3]) | (i32::from(d[4] & 0x0f) << 8);
+    let v_active = i32::from(d[5]) | (i32::from(d[7] & 0xf0) << 4);
+    let v_blank = i32::from(d[6]) | (i32::from(d[7] & 0x0f) << 8);
+    let width_mm = i32::from(d[12]) | (i32::from(d[14] & 0xf0) << 4);
+    let height_mm = i32::from(d[13]) | (i32::from(d[14] & 0x0f) << 8);
+
+    let total = f64::from((h_active + h_blank) * (v_active + v_blank));
+    let mode = (h_active > 0 && v_active > 0 && total > 0.0).then(|| MonitorMode {
+        width: h_active,
+        height: v_active,
+        refresh_rate: clock / total,
+    });
+    (mode, (width_mm, height_mm))
+}
+
+/// Text of a name/serial descriptor: up to 13 bytes ended by a newline.
+fn descriptor_text(d: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(&d[5..18]);
+    let text = text.split('\n').next().unwrap_or_default().trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dell U2720Q: 3840x2160 detailed timing, range limits, name and
+    /// serial descriptors.
+    const DELL: &str = "00ffffffffffff0010aca64135324c4c0c1e0104b53c22783a000000000000000000000000000101010101010101\
+                        01010101010101014dd000a0f0703e803020350055502100001e000000fd00184c1e8c3c000a202020202020000000\
+                        fc0044454c4c205532373230510a20000000ff004632544e4731330a202020202000c5";
+
+    /// Laptop panel: no name descriptor, only a numeric serial and two
+    /// unspecified-text descriptors.
+    const PANEL: &str = "00ffffffffffff0009e51c0a570400000c1e0104b51e13783a000000000000000000000000000101010101010101\
+                         0101010101010101b28940a0b0083470302036002ebd1000001e000000fe00424f452043510a202020202020000000\
+                         fe004e4531343051444d2d4e36410a0000000000000000000000000000000000000054";
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2).map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_monitor() {
+        let edid = parse(&bytes(DELL)).unwrap();
+        assert_eq!(edid.manufacturer, "DEL");
+        assert_eq!(edid.product_code, 0x41a6);
+        assert_eq!(edid.name.as_deref(), Some("DELL U2720Q"));
+        assert_eq!(edid.serial.as_deref(), Some("F2TNG13"));
+        assert_eq!(edid.model(), "Dell U2720Q");
+        assert_eq!((edid.width_mm, edid.height_mm), (597, 336));
+        assert!((edid.diagonal_inches().unwrap() - 26.97).abs() < 0.01);
+        assert!((edid.dpi(3840).unwrap() - 163.4).abs() < 0.1);
+        assert_eq!(edid.recommended_scale(3840, 2160, false), Some(1.5));
+    }
+
+    #[test]
+    fn parses_panel_without_name() {
+        let edid = parse(&bytes(PANEL)).unwrap();
+        assert_eq!(edid.manufacturer, "BOE");
+        assert_eq!(edid.name, None);
+        assert_eq!(edid.serial.as_deref(), Some("1111"));
+        assert_eq!(edid.model(), "BOE 0A1C");
+        assert_eq!((edid.width_mm, edid.height_mm), (302, 189));
+    }
+
+    #[test]
+    fn detailed_timing_gives_preferred_mode() {
+        let dell = parse(&bytes(DELL)).unwrap().preferred.unwrap();
+        assert_eq!((dell.width, dell.height), (3840, 2160));
+        // 533.25 MHz / (4000 x 2222)
+        assert!((dell.refresh_rate - 59.997).abs() < 0.001);
+
+        let panel = parse(&bytes(PANEL)).unwrap().preferred.unwrap();
+        assert_eq!((panel.width, panel.height), (2880, 1800));
+        assert!((panel.refresh_rate - 62.61).abs() < 0.01);
+    }
+
+    #[test]
+    fn falls_back_to_size_in_cm() {
+        let mut blob = bytes(DELL);
+        // Zero the image size in the detailed timing.
+        blob[54 + 12..54 + 15].fill(0);
+        let edid = parse(&blob).unwrap();
+        assert_eq!((edid.width_mm, edid.height_mm), (600, 340));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse(&bytes(DELL)[..100]).is_err());
+        let mut blob = bytes(DELL);
+        blob[0] = 0x42;
+        assert!(parse(&blob).is_err());
+        assert!(from_hex("not hex").is_none());
+        assert_eq!(from_hex(DELL).unwrap().product_code, 0x41a6);
+    }
+}
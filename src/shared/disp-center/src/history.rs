@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+/// Steps kept for undo; older ones are dropped.
+const MAX_STEPS: usize = 100;
+
+/// Edits of the same group this close together become one step, so a
+/// slider drag is undone in one go.
+const MERGE_WINDOW: Duration = Duration::from_millis(1000);
+
+/// Undo/redo stacks of snapshots, each labelled with the edit that left it
+/// ("moved HDMI-A-1"). The caller owns the current state and swaps it with
+/// the snapshot on undo and redo.
+pub struct History<T> {
+    undo: Vec<(String, T)>,
+    redo: Vec<(String, T)>,
+    /// Group and time of the last recorded edit, while it can still merge.
+    last: Option<(String, Instant)>,
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), last: None }
+    }
+
+    /// The state an edit of `group` would merge into: the one before the
+    /// last step, if that step was of the same group and just now.
+    pub fn merging(&self, group: &str) -> Option<&T> {
+        let (last, at) = self.last.as_ref()?;
+        let step = self.undo.last().filter(|_| last == group && at.elapsed() < MERGE_WINDOW)?;
+        Some(&step.1)
+    }
+
+    /// Record an edit: `before` is the state it changed. Edits in the same
+    /// `group` merge (see `merging`) and only update the description. A new
+    /// edit discards the steps that were undone.
+    pub fn record(&mut self, description: impl Into<String>, before: T, group: Option<&str>) {
+        let description = description.into();
+        let merge = group.is_some_and(|g| self.merging(g).is_some());
+        match self.undo.last_mut() {
+            Some(step) if merge => step.0 = description,
+            _ => {
+                self.undo.push((description, before));
+                if self.undo.len() > MAX_STEPS {
+                    self.undo.remove(0);
+                }
+            }
+        }
+        self.last = group.map(|g| (g.to_string(), Instant::now()));
+        self.redo.clear();
+    }
+
+    /// Step back: returns the edit's description and the state before it,
+    /// and keeps `current` for redo.
+    pub fn undo(&mut self, current: T) -> Option<(String, T)> {
+        let (description, before) = self.undo.pop()?;
+        self.last = None;
+        self.redo.push((description.clone(), current));
+        Some((description, before))
+    }
+
+    /// Step forward again: returns the edit's description and the state
+    /// after it, and keeps `current` for undo.
+    pub fn redo(&mut self, current: T) -> Option<(String, T)> {
+        let (description, after) = self.redo.pop()?;
+        self.last = None;
+        self.undo.push((description.clone(), current));
+        Some((description, after))
+    }
+
+    /// Description of the edit `undo` would revert.
+    pub fn undo_description(&self) -> Option<&str> {
+        self.undo.last().map(|(d, _)| d.as_str())
+    }
+
+    /// Description of the edit `redo` would repeat.
+    pub fn redo_description(&self) -> Option<&str> {
+        self.redo.last().map(|(d, _)| d.as_str())
+    }
+
+    /// Forget all steps, e.g. when the monitors were queried afresh.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_of_one_group_merge() {
+        let mut history = History::new();
+        history.record("scale DP-1", 10, Some("scale:DP-1"));
+        assert_eq!(history.merging("scale:DP-1"), Some(&10));
+        assert_eq!(history.merging("move:DP-1"), None);
+        history.record("scale DP-1 to 1.5", 11, Some("scale:DP-1"));
+        history.record("scale DP-1 to 2", 12, Some("scale:DP-1"));
+        assert_eq!(history.undo_description(), Some("scale DP-1 to 2"));
+
+        // One step back to the state before the drag started.
+        assert_eq!(history.undo(13), Some(("scale DP-1 to 2".to_string(), 10)));
+        assert_eq!(history.undo(10), None);
+    }
+
+    #[test]
+    fn other_groups_and_ungrouped_edits_dont_merge() {
+        let mut history = History::new();
+        history.record("scale DP-1", 1, Some("scale:DP-1"));
+        history.record("move DP-1", 2, Some("move:DP-1"));
+        history.record("disable DP-2", 3, None);
+        history.record("disable DP-3", 4, None);
+        assert_eq!(history.merging("move:DP-1"), None);
+        let steps: Vec<i32> = std::iter::from_fn(|| history.undo(0).map(|(_, before)| before)).collect();
+        assert_eq!(steps, [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn edits_after_the_merge_window_are_new_steps() {
+        let mut history = History::new();
+        history.record("move DP-1", 1, Some("move:DP-1"));
+        let earlier = Instant::now().checked_sub(MERGE_WINDOW * 2).unwrap();
+        history.last = Some(("move:DP-1".into(), earlier));
+        assert_eq!(history.merging("move:DP-1"), None);
+        history.record("move DP-1 again", 2, Some("move:DP-1"));
+        assert_eq!(history.undo(3).map(|(_, before)| before), Some(2));
+        assert_eq!(history.undo(2).map(|(_, before)| before), Some(1));
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut history = History::new();
+        let mut state = 0;
+        for (description, next) in [("one", 1), ("two", 2), ("three", 3)] {
+            history.record(description, state, None);
+            state = next;
+        }
+
+        for expected in [2, 1, 0] {
+            let (_, before) = history.undo(state).unwrap();
+            assert_eq!(before, expected);
+            state = before;
+        }
+        assert_eq!(history.undo_description(), None);
+        assert_eq!(history.redo_description(), Some("one"));
+
+        for expected in [1, 2, 3] {
+            let (_, after) = history.redo(state).unwrap();
+            assert_eq!(after, expected);
+            state = after;
+        }
+        assert_eq!(history.redo(state), None);
+        assert_eq!(history.undo_description(), Some("three"));
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut history = History::new();
+        history.record("one", 0, None);
+        history.record("two", 1, None);
+        assert_eq!(history.undo(2), Some(("two".to_string(), 1)));
+        assert_eq!(history.redo_description(), Some("two"));
+
+        history.record("other", 1, None);
+        assert_eq!(history.redo_description(), None);
+        assert_eq!(history.redo(5), None);
+        assert_eq!(history.undo_description(), Some("other"));
+    }
+
+    #[test]
+    fn undo_doesnt_merge_into_the_next_edit() {
+        let mut history = History::new();
+        history.record("move DP-1", 0, Some("move:DP-1"));
+        history.undo(1);
+        history.redo(0);
+        assert_eq!(history.merging("move:DP-1"), None);
+    }
+
+    #[test]
+    fn keeps_at_most_max_steps() {
+        let mut history = History::new();
+        for i in 0..MAX_STEPS + 5 {
+            history.record(format!("edit {i}"), i, None);
+        }
+        let steps: Vec<usize> = std::iter::from_fn(|| history.undo(0).map(|(_, before)| before)).collect();
+        assert_eq!(steps.len(), MAX_STEPS);
+        // The oldest ones went first.
+        assert_eq!(steps.last(), Some(&5));
+        assert_eq!(steps.first(), Some(&(MAX_STEPS + 4)));
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut history = History::new();
+        history.record("one", 0, Some("move:DP-1"));
+        history.record("two", 1, None);
+        history.undo(2);
+        history.clear();
+        assert_eq!(history.undo_description(), None);
+        assert_eq!(history.redo_description(), None);
+        assert_eq!(history.merging("move:DP-1"), None);
+    }
+}
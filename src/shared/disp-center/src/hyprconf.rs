@@ -0,0 +1,607 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::monitor::{MonitorConfig, OutputOptions, WorkspaceRule, COLOR_MODES, WORKSPACES};
+
+const HEADER: &str = "# Monitor layout. disp-center rewrites the `monitor =` lines of the outputs\n\
+                      # it configures, the monitor of the workspaces it assigns and its lid\n\
+                      # switch bindings, and leaves every other line alone.\n";
+
+/// Header written by versions that overwrote the whole file.
+const OLD_HEADER: &str = "# Generated by disp-center";
+
+/// Switch name libinput gives laptop lids.
+const LID_SWITCH: &str = "Lid Switch";
+
+/// Backups of monitors.conf kept next to it.
+const MAX_BACKUPS: usize = 5;
+
+/// Split a hyprlang line into code and comment. `#` starts a comment,
+/// `##` is a literal `#`.
+fn split_comment(line: &str) -> (&str, &str) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'#' {
+            if bytes.get(i + 1) == Some(&b'#') {
+                i += 2;
+                continue;
+            }
+            return (&line[..i], &line[i..]);
+        }
+        i += 1;
+    }
+    (line, "")
+}
+
+/// Connector named by a top-level `monitor = NAME, ...` line that sets a
+/// mode (or disables the output). `addreserved` lines and `desc:` rules
+/// are not ours.
+fn monitor_rule_name(code: &str) -> Option<&str> {
+    let (key, value) = code.split_once('=')?;
+    if key.trim() != "monitor" {
+        return None;
+    }
+    let mut fields = value.split(',').map(str::trim);
+    let name = fields.next()?;
+    if name.is_empty() || name.starts_with("desc:") || fields.next() == Some("addreserved") {
+        return None;
+    }
+    Some(name)
+}
+
+/// Options set on `name`'s first top-level monitor line in `content`
+/// (`vrr, 2, bitdepth, 10, ...` after the position and scale), with
+/// defaults for the ones left out. None without such a line. hyprctl only
+/// reports whether VRR is active right now, so the line is what tells
+/// "fullscreen only" apart.
+pub fn configured_options(content: &str, name: &str) -> Option<OutputOptions> {
+    let mut depth = 0i32;
+    let code = content.lines().map(|line| split_comment(line).0).find(|code| {
+        let top_level = depth == 0;
+        depth += code.matches('{').count() as i32 - code.matches('}').count() as i32;
+        top_level && monitor_rule_name(code) == Some(name)
+    })?;
+    let (_, value) = code.split_once('=')?;
+    let fields: Vec<&str> = value.split(',').map(str::trim).collect();
+    if fields.get(1) == Some(&"disable") {
+        return None;
+    }
+
+    let mut options = OutputOptions::default();
+    for pair in fields.get(4..).unwrap_or_default().chunks(2) {
+        let [key, value] = pair else { continue };
+        match *key {
+            "vrr" => options.vrr = value.parse().unwrap_or(0),
+            "bitdepth" => options.ten_bit = *value == "10",
+            "cm" if COLOR_MODES.contains(value) => options.color_mode = value.to_string(),
+            "sdrbrightness" => options.sdr_brightness = value.parse().unwrap_or(1.0),
+            _ => {}
+        }
+    }
+    Some(options)
+}
+
+/// Workspace number of a top-level `workspace = N, ...` line for one of
+/// the managed workspaces, with the line's other fields.
+fn workspace_rule(code: &str) -> Option<(i32, Vec<&str>)> {
+    let (key, value) = code.split_once('=')?;
+    if key.trim() != "workspace" {
+        return None;
+    }
+    let mut fields = value.split(',').map(str::trim);
+    let id: i32 = fields.next()?.parse().ok()?;
+    if !(1..=WORKSPACES).contains(&id) {
+        return None;
+    }
+    Some((id, fields.filter(|f| !f.is_empty()).collect()))
+}
+
+/// Output a top-level `bindl = , switch:...:Lid Switch, exec, hyprctl
+/// keyword monitor "NAME, ..."` line turns on or off. Other lid bindings
+/// (locking, suspending) are not ours.
+fn lid_binding_output(code: &str) -> Option<&str> {
+    let (key, value) = code.split_once('=')?;
+    if key.trim() != "bindl" || !value.contains(&format!(":{LID_SWITCH},")) {
+        return None;
+    }
+    let (_, rule) = value.split_once("hyprctl keyword monitor")?;
+    let name = rule.trim().trim_start_matches('"').split(',').next()?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+/// The `bindl` lines that turn `internal` off when the lid closes and back
+/// on with its settings when it opens.
+pub fn lid_bindings(internal: &MonitorConfig) -> [String; 2] {
+    let on = MonitorConfig { enabled: true, ..internal.clone() }.to_hyprland_line();
+    let on = on.trim_start_matches("monitor = ");
+    [
+        format!(
+            "bindl = , switch:on:{LID_SWITCH}, exec, hyprctl keyword monitor \"{}, disable\"",
+            internal.name
+        ),
+        format!("bindl = , switch:off:{LID_SWITCH}, exec, hyprctl keyword monitor \"{on}\""),
+    ]
+}
+
+/// Whether `content` turns an output off when the lid closes.
+pub fn has_lid_bindings(content: &str) -> bool {
+    content.lines().any(|line| lid_binding_output(split_comment(line).0).is_some())
+}
+
+/// Replace the lid bindings in `content` with the ones for `internal`, or
+/// remove them. New bindings go where the old ones were, else at the end.
+pub fn with_lid_bindings(content: &str, internal: Option<&MonitorConfig>) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut insert_at: Option<usize> = None;
+    for line in content.lines() {
+        if lid_binding_output(split_comment(line).0).is_some() {
+            insert_at.get_or_insert(out.len());
+            continue;
+        }
+        out.push(line.to_string());
+    }
+    if let Some(internal) = internal {
+        let at = insert_at.unwrap_or_else(|| {
+            if out.is_empty() {
+                out.extend(HEADER.lines().map(str::to_string));
+            }
+            if out.last().is_some_and(|l| !l.trim().is_empty()) {
+                out.push(String::new());
+            }
+            out.len()
+        });
+        out.splice(at..at, lid_bindings(internal));
+    } else if insert_at == Some(out.len()) {
+        // Drop the blank line that separated them at the end of the file.
+        while out.last().is_some_and(|l| l.trim().is_empty()) {
+            out.pop();
+        }
+    }
+
+    let mut content = out.join("\n");
+    content.push('\n');
+    content
+}
+
+/// `workspace = N, monitor:NAME, default:true[, other rules]`; without a
+/// monitor only the other rules stay (None if there are none).
+fn workspace_line(id: i32, monitor: Option<&str>, other: &[&str]) -> Option<String> {
+    let mut fields: Vec<String> = Vec::new();
+    if let Some(monitor) = monitor {
+        fields.push(format!("monitor:{monitor}"));
+        fields.push("default:true".into());
+    }
+    fields.extend(
+        other
+            .iter()
+            .filter(|f| !f.starts_with("monitor:") && !f.starts_with("default:"))
+            .map(|f| f.to_string()),
+    );
+    if fields.is_empty() {
+        return None;
+    }
+    Some(format!("workspace = {id}, {}", fields.join(", ")))
+}
+
+/// Rewrite the monitor lines for `configs` and the monitor of the
+/// workspaces in `workspaces` in `existing`, keeping every other line
+/// (comments, other rules, variables, categories, rules for outputs that
+/// aren't connected or workspaces that aren't listed) byte for byte. Each
+/// output's first rule is replaced in place with its inline comment kept;
+/// later duplicates are dropped. A listed workspace's first line gets the
+/// monitor (none for an empty one) and keeps its other rules (gaps, layout
+/// options, ...); a line that already names that monitor is left alone.
+/// Lid bindings restore the output's new settings. Missing lines are added
+/// after the last monitor or workspace line.
+pub fn update(existing: &str, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut written: Vec<&str> = Vec::new();
+    let mut written_ws: Vec<i32> = Vec::new();
+    let mut insert_at: Option<usize> = None;
+    let mut ws_insert_at: Option<usize> = None;
+    let mut depth = 0i32;
+
+    for (i, line) in existing.lines().enumerate() {
+        if i == 0 && line.starts_with(OLD_HEADER) {
+            out.extend(HEADER.lines().map(str::to_string));
+            continue;
+        }
+        let (code, comment) = split_comment(line);
+        let top_level = depth == 0;
+        depth += code.matches('{').count() as i32 - code.matches('}').count() as i32;
+
+        if let Some((id, other)) = workspace_rule(code).filter(|_| top_level) {
+            let new = match workspaces.iter().find(|w| w.workspace == id) {
+                None => Some(line.to_string()),
+                Some(rule) => {
+                    // Only the first line for a workspace gets the monitor.
+                    let monitor =
+                        Some(rule.monitor.as_str()).filter(|m| !m.is_empty() && !written_ws.contains(&id));
+                    written_ws.push(id);
+                    if monitor.is_some_and(|m| other.contains(&format!("monitor:{m}").as_str())) {
+                        Some(line.to_string())
+                    } else {
+                        workspace_line(id, monitor, &other).map(|mut new| {
+                            if !comment.is_empty() {
+                                new.push(' ');
+                                new.push_str(comment);
+                            }
+                            new
+                        })
+                    }
+                }
+            };
+            out.extend(new);
+            ws_insert_at = Some(out.len());
+            continue;
+        }
+
+        let lid_output = if top_level { lid_binding_output(code) } else { None };
+        if let Some(config) = lid_output.and_then(|name| configs.iter().find(|c| c.name == name && c.enabled)) {
+            let [off, on] = lid_bindings(config);
+            let mut new = if code.contains("switch:on:") { off } else { on };
+            if !comment.is_empty() {
+                new.push(' ');
+                new.push_str(comment);
+            }
+            out.push(new);
+            continue;
+        }
+
+        let rule = if top_level { monitor_rule_name(code) } else { None };
+        let Some(name) = rule else {
+            out.push(line.to_string());
+            continue;
+        };
+        let Some(config) = configs.iter().find(|c| c.name == name) else {
+            out.push(line.to_string());
+            insert_at = Some(out.len());
+            continue;
+        };
+        if written.contains(&config.name.as_str()) {
+            continue;
+        }
+        written.push(&config.name);
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut new = format!("{indent}{}", config.to_hyprland_line());
+        if !comment.is_empty() {
+            new.push(' ');
+            new.push_str(comment);
+        }
+        out.push(new);
+        insert_at = Some(out.len());
+    }
+
+    let missing: Vec<String> = configs
+        .iter()
+        .filter(|c| !written.contains(&c.name.as_str()))
+        .map(MonitorConfig::to_hyprland_line)
+        .collect();
+    if out.is_empty() {
+        out.extend(HEADER.lines().map(str::to_string));
+        out.push(String::new());
+    }
+    let at = insert_at.unwrap_or(out.len());
+    let added = missing.len();
+    out.splice(at..at, missing);
+
+    let missing_ws: Vec<String> = workspaces
+        .iter()
+        .filter(|w| !written_ws.contains(&w.workspace) && !w.monitor.is_empty())
+        .filter_map(|w| workspace_line(w.workspace, Some(&w.monitor), &[]))
+        .collect();
+    let at = match ws_insert_at {
+        Some(i) if i >= at => i + added,
+        Some(i) => i,
+        // Right after the monitor lines; a blank line first at the end of the file.
+        None if !missing_ws.is_empty() && at + added == out.len() => {
+            out.push(String::new());
+            out.len()
+        }
+        None => at + added,
+    };
+    out.splice(at..at, missing_ws);
+
+    let mut content = out.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Update the monitor and workspace lines of `path` (see `update`).
+pub fn write(path: &Path, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> Result<(), String> {
+    replace(path, |existing| update(existing, configs, workspaces))
+}
+
+/// Add the lid bindings for `internal` to `path`, or remove them.
+pub fn write_lid(path: &Path, internal: Option<&MonitorConfig>) -> Result<(), String> {
+    replace(path, |existing| with_lid_bindings(existing, internal))
+}
+
+/// Rewrite `path` in place: back up the current file, then write the new
+/// content to a temporary file and rename it over the original so a crash
+/// never leaves a half-written config. Symlinks (dotfile managers) are
+/// followed and the file keeps its permissions. Used for every config file
+/// disp-center edits, not only monitors.conf.
+pub fn replace(path: &Path, edit: impl FnOnce(&str) -> String) -> Result<(), String> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let existing = std::fs::read_to_string(&target).unwrap_or_default();
+    let content = edit(&existing);
+    if content == existing {
+        return Ok(());
+    }
+
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    if !existing.is_empty() {
+        backup(&target)?;
+    }
+
+    let file_name = target.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let tmp = target.with_file_name(format!(".{file_name}.tmp"));
+    std::fs::write(&tmp, &content).map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+    if let Ok(meta) = std::fs::metadata(&target) {
+        let _ = std::fs::set_permissions(&tmp, meta.permissions());
+    }
+    if let Ok(file) = std::fs::File::open(&tmp) {
+        let _ = file.sync_all();
+    }
+    std::fs::rename(&tmp, &target).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("Failed to replace {}: {e}", target.display())
+    })
+}
+
+/// Copy `path` to `<name>.<timestamp>.bak` and delete all but the newest
+/// `MAX_BACKUPS` backups.
+fn backup(path: &Path) -> Result<(), String> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    // Saves within the same microsecond step the time on, so every save
+    // gets its own backup and the names still sort by age.
+    let mut time = SystemTime::now();
+    let backup = loop {
+        let candidate = path.with_file_name(format!("{file_name}.{}.bak", timestamp(time)));
+        if !candidate.exists() {
+            break candidate;
+        }
+        time += Duration::from_micros(1);
+    };
+    std::fs::copy(path, &backup)
+        .map_err(|e| format!("Failed to back up {} to {}: {e}", path.display(), backup.display()))?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let prefix = format!("{file_name}.");
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .map(|n| n.to_string_lossy())
+                        .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".bak"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Timestamps sort chronologically as text.
+    backups.sort();
+    let excess = backups.len().saturating_sub(MAX_BACKUPS);
+    for old in &backups[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// UTC `time` as `YYYYMMDD-HHMMSS.uuuuuu` (microseconds).
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}.{:06}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::Monitor;
+
+    fn config(name: &str, width: i32, height: i32, x: i32) -> MonitorConfig {
+        MonitorConfig::from_monitor(&Monitor {
+            name: name.into(),
+            width,
+            height,
+            refresh_rate: 60.0,
+            x,
+            scale: 1.0,
+            enabled: true,
+            ..Monitor::default()
+        })
+    }
+
+    fn ws(workspace: i32, monitor: &str) -> WorkspaceRule {
+        WorkspaceRule { workspace, monitor: monitor.into() }
+    }
+
+    #[test]
+    fn replaces_existing_line_in_place() {
+        let existing = "# my monitors\n\
+                        $main = DP-1\n\
+                        monitor = DP-1, preferred, auto, 1 # desk\n\
+                        monitor = DP-1, 1920x1080@60, 0x0, 1\n\
+                        monitor = , preferred, auto, 1\n";
+        let dp = config("DP-1", 2560, 1440, 0);
+        let updated = update(existing, std::slice::from_ref(&dp), &[]);
+        assert_eq!(
+            updated,
+            format!(
+                "# my monitors\n$main = DP-1\n{} # desk\nmonitor = , preferred, auto, 1\n",
+                dp.to_hyprland_line()
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_other_outputs_and_nested_rules() {
+        let existing = "monitor = HDMI-A-2, 1920x1080@60, 0x0, 1\n\
+                        monitor = desc:Dell Inc. U2720Q, preferred, auto, 1.5\n\
+                        monitor = DP-1, addreserved, 0, 40, 0, 0\n\
+                        plugin {\n    monitor = DP-1, preferred, auto, 2\n}\n";
+        let updated = update(existing, &[config("DP-1", 2560, 1440, 0)], &[]);
+        for line in existing.lines() {
+            assert!(updated.lines().any(|l| l == line), "lost {line:?}");
+        }
+    }
+
+    #[test]
+    fn appends_missing_monitor_after_the_others() {
+        let existing = "monitor = DP-1, preferred, auto, 1\n\nbind = SUPER, Q, killactive\n";
+        let dp = config("DP-1", 2560, 1440, 0);
+        let hdmi = config("HDMI-A-1", 1920, 1080, 2560);
+        let updated = update(existing, &[dp.clone(), hdmi.clone()], &[]);
+        let lines: Vec<&str> = updated.lines().collect();
+        assert_eq!(lines[0], dp.to_hyprland_line());
+        assert_eq!(lines[1], hdmi.to_hyprland_line());
+        assert_eq!(&lines[2..], ["", "bind = SUPER, Q, killactive"]);
+    }
+
+    #[test]
+    fn empty_file_gets_header() {
+        let dp = config("DP-1", 2560, 1440, 0);
+        let updated = update("", std::slice::from_ref(&dp), &[ws(1, "DP-1")]);
+        assert!(updated.starts_with(HEADER));
+        assert!(updated.contains(&dp.to_hyprland_line()));
+        assert!(updated.ends_with("\nworkspace = 1, monitor:DP-1, default:true\n"));
+    }
+
+    #[test]
+    fn old_header_is_replaced() {
+        let existing = format!("{OLD_HEADER} -- do not edit\nmonitor = DP-1, preferred, auto, 1\n");
+        let updated = update(&existing, &[config("DP-1", 2560, 1440, 0)], &[]);
+        assert!(updated.starts_with(HEADER));
+        assert!(!updated.contains(OLD_HEADER));
+    }
+
+    #[test]
+    fn workspace_lines_keep_other_rules() {
+        let existing = "workspace = 1, monitor:HDMI-A-1, default:true, gapsout:0 # big\n\
+                        workspace = 2, monitor:HDMI-A-1\n\
+                        workspace = 11, monitor:HDMI-A-1\n";
+        let updated = update(existing, &[], &[ws(1, "DP-1")]);
+        assert_eq!(
+            updated,
+            "workspace = 1, monitor:DP-1, default:true, gapsout:0 # big\n\
+             workspace = 2, monitor:HDMI-A-1\n\
+             workspace = 11, monitor:HDMI-A-1\n"
+        );
+    }
+
+    #[test]
+    fn unlisted_workspace_lines_stay_byte_for_byte() {
+        let existing = "workspace = 2,monitor:HDMI-A-1 ,  default:true\n\
+                        workspace = 3, monitor:DP-1, gapsin:4 # mine\n\
+                        workspace = special:scratch, on-created-empty:kitty\n";
+        assert_eq!(update(existing, &[], &[]), existing);
+        // Listed with the monitor it already has: left alone too.
+        assert_eq!(update(existing, &[], &[ws(2, "HDMI-A-1"), ws(3, "DP-1")]), existing);
+    }
+
+    #[test]
+    fn empty_monitor_unassigns() {
+        let existing = "workspace = 2, monitor:HDMI-A-1, default:true, gapsout:0\n\
+                        workspace = 3, monitor:DP-1, default:true\n";
+        assert_eq!(
+            update(existing, &[], &[ws(2, ""), ws(3, ""), ws(4, "")]),
+            "workspace = 2, gapsout:0\n"
+        );
+    }
+
+    #[test]
+    fn lid_bindings_added_and_removed() {
+        let existing = "monitor = eDP-1, preferred, auto, 2\n";
+        let edp = config("eDP-1", 2880, 1800, 0);
+        let with = with_lid_bindings(existing, Some(&edp));
+        assert!(has_lid_bindings(&with));
+        assert_eq!(with.lines().filter(|l| l.starts_with("bindl")).count(), 2);
+        assert_eq!(with_lid_bindings(&with, None), existing);
+    }
+
+    #[test]
+    fn timestamps_sort_by_time() {
+        let at = |secs: u64, micros: u64| timestamp(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_micros(micros));
+        assert_eq!(at(0, 0), "19700101-000000.000000");
+        assert_eq!(at(1_792_324_861, 42), "20261018-120101.000042");
+        assert!(at(1_792_324_861, 42) < at(1_792_324_861, 43));
+        assert!(at(1_792_324_861, 999_999) < at(1_792_324_862, 0));
+    }
+
+    #[test]
+    fn backups_are_rotated() {
+        let dir = std::env::temp_dir().join(format!("disp-center-hyprconf-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("monitors.conf");
+        // Faster than the clock ticks; each save still gets a backup.
+        for x in 0..MAX_BACKUPS as i32 + 3 {
+            write(&path, &[config("DP-1", 1920, 1080, x)], &[]).unwrap();
+        }
+        let mut backups: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| n.ends_with(".bak"))
+            .collect();
+        backups.sort();
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        // The newest backup holds the save before the last one.
+        let newest = std::fs::read_to_string(dir.join(backups.last().unwrap())).unwrap();
+        assert!(newest.contains(&config("DP-1", 1920, 1080, MAX_BACKUPS as i32 + 1).to_hyprland_line()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn options_from_monitor_line() {
+        let conf = "monitor = DP-1, 2560x1440@165, 0x0, 1, transform, 0, vrr, 2, bitdepth, 10 # desk\n\
+                    monitor = HDMI-A-1, 1920x1080@60, 2560x0, 1\n\
+                    monitor = eDP-1, disable\n";
+        let dp = configured_options(conf, "DP-1").unwrap();
+        assert_eq!(dp.vrr, 2);
+        assert!(dp.ten_bit);
+        assert_eq!(dp.color_mode, "auto");
+        assert_eq!(configured_options(conf, "HDMI-A-1").unwrap().vrr, 0);
+        assert!(configured_options(conf, "eDP-1").is_none());
+        assert!(configured_options(conf, "DP-2").is_none());
+    }
+
+    #[test]
+    fn options_ignore_nested_and_commented_lines() {
+        let conf = "# monitor = DP-1, preferred, auto, 1, vrr, 1\n\
+                    plugin {\n    monitor = DP-1, preferred, auto, 1, vrr, 1\n}\n\
+                    monitor = DP-1, preferred, auto, 1.25, cm, hdr, sdrbrightness, 1.20\n";
+        let dp = configured_options(conf, "DP-1").unwrap();
+        assert_eq!(dp.vrr, 0);
+        assert_eq!(dp.color_mode, "hdr");
+        assert!((dp.sdr_brightness - 1.2).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,16 @@
 use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
 use std::process::Command;
 
 use crate::backend::DisplayBackend;
-use crate::monitor::{Monitor, MonitorConfig, MonitorMode};
+use crate::edid;
+use crate::hyprconf;
+use crate::layout::format_scale;
+use crate::lid::{self, LidPolicy};
+use crate::monitor::{
+    Monitor, MonitorConfig, MonitorMode, OutputOptions, WorkspaceRule, COLOR_MODES, WORKSPACES,
+};
 
 pub struct HyprlandBackend;
 
@@ -26,7 +34,44 @@ impl HyprlandBackend {
     }
 }
 
-/// Raw JSON shape from `hyprctl monitors -j`.
+/// Hyprland's event socket (`.socket2.sock`) for the running instance.
+pub fn event_socket_path() -> Result<std::path::PathBuf, String> {
+    let sig = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .map_err(|_| "HYPRLAND_INSTANCE_SIGNATURE is not set".to_string())?;
+    let runtime = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".into());
+    let path = std::path::PathBuf::from(runtime).join("hypr").join(&sig).join(".socket2.sock");
+    if path.exists() {
+        return Ok(path);
+    }
+    // Hyprland before 0.40 kept its sockets in /tmp.
+    Ok(std::path::PathBuf::from("/tmp/hypr").join(&sig).join(".socket2.sock"))
+}
+
+/// `~/.config/hypr/monitors.conf`, written by `persist`.
+fn monitors_conf_path() -> std::path::PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("~/.config"))
+        .join("hypr")
+        .join("monitors.conf")
+}
+
+/// Whether a socket2 event line ("monitoradded>>DP-1") reports an output
+/// being connected or disconnected.
+pub fn is_hotplug_event(line: &str) -> bool {
+    let event = line.trim_end().split(">>").next().unwrap_or_default();
+    matches!(event, "monitoradded" | "monitoraddedv2" | "monitorremoved" | "monitorremovedv2")
+}
+
+/// Raw JSON shape from `hyprctl workspacerules -j`.
+#[derive(Debug, Deserialize)]
+struct HyprWorkspaceRule {
+    #[serde(rename = "workspaceString")]
+    workspace: String,
+    #[serde(default)]
+    monitor: Option<String>,
+}
+
+/// Raw JSON shape from `hyprctl monitors all -j`.
 #[derive(Debug, Deserialize)]
 struct HyprMonitor {
     id: i32,
@@ -46,6 +91,16 @@ struct HyprMonitor {
     disabled: bool,
     #[serde(rename = "availableModes")]
     available_modes: Vec<String>, // "3840x2160@143.99Hz"
+    #[serde(rename = "mirrorOf", default)]
+    mirror_of: String, // "none" or the mirrored monitor's name
+    #[serde(default)]
+    vrr: bool,
+    #[serde(rename = "currentFormat", default)]
+    current_format: String, // "XRGB8888", "XRGB2101010", ...
+    #[serde(rename = "colorManagementPreset", default)]
+    color_management_preset: String,
+    #[serde(rename = "sdrBrightness", default)]
+    sdr_brightness: Option<f64>,
 }
 
 fn parse_mode(s: &str) -> Option<MonitorMode> {
@@ -62,23 +117,43 @@ fn parse_mode(s: &str) -> Option<MonitorMode> {
 
 impl DisplayBackend for HyprlandBackend {
     fn query_monitors(&self) -> Result<Vec<Monitor>, String> {
-        let json = self.hyprctl(&["monitors", "-j"])?;
+        // `all` includes disabled outputs, so a profile that turns one off
+        // still has it in its monitor set.
+        let json = self.hyprctl(&["monitors", "all", "-j"])?;
         let raw: Vec<HyprMonitor> =
             serde_json::from_str(&json).map_err(|e| format!("Failed to parse monitors JSON: {e}"))?;
 
+        // Options come from monitors.conf where it has a line for the output.
+        let conf = std::fs::read_to_string(monitors_conf_path()).unwrap_or_default();
+
         let monitors = raw
             .into_iter()
             .map(|m| {
                 let available_modes: Vec<MonitorMode> =
                     m.available_modes.iter().filter_map(|s| parse_mode(s)).collect();
 
+                // A disabled output may report no size; show its preferred mode.
+                let (width, height, refresh_rate) = match available_modes.first() {
+                    Some(mode) if m.width <= 0 || m.height <= 0 => (mode.width, mode.height, mode.refresh_rate),
+                    _ => (m.width, m.height, m.refresh_rate),
+                };
+                let edid = edid::for_connector(&m.name);
+                let options = hyprconf::configured_options(&conf, &m.name).unwrap_or_else(|| OutputOptions {
+                    vrr: m.vrr as i32,
+                    ten_bit: m.current_format.contains("2101010"),
+                    color_mode: Some(m.color_management_preset.clone())
+                        .filter(|p| COLOR_MODES.contains(&p.as_str()))
+                        .unwrap_or_else(|| "auto".into()),
+                    sdr_brightness: m.sdr_brightness.unwrap_or(1.0),
+                });
+
                 Monitor {
                     id: m.id,
                     name: m.name,
                     description: m.description,
-                    width: m.width,
-                    height: m.height,
-                    refresh_rate: m.refresh_rate,
+                    width,
+                    height,
+                    refresh_rate,
                     x: m.x,
                     y: m.y,
                     scale: m.scale,
@@ -86,7 +161,11 @@ impl DisplayBackend for HyprlandBackend {
                     enabled: !m.disabled,
                     dpms: m.dpms_status,
                     focused: m.focused,
+                    mirror: Some(m.mirror_of).filter(|t| !t.is_empty() && t != "none"),
+                    options,
+                    edid,
                     available_modes,
+                    custom_mode: None,
                 }
             })
             .collect();
@@ -101,10 +180,18 @@ impl DisplayBackend for HyprlandBackend {
                 if !c.enabled {
                     format!("keyword monitor {},disable", c.name)
                 } else {
-                    format!(
-                        "keyword monitor {},{}x{}@{:.2},{}x{},{:.2}",
-                        c.name, c.width, c.height, c.refresh_rate, c.x, c.y, c.scale,
-                    )
+                    let mut rule = format!(
+                        "keyword monitor {},{},{}x{},{},transform,{}",
+                        c.name, c.hyprland_mode(), c.x, c.y,
+                        format_scale(c.scale), c.transform,
+                    );
+                    if let Some(target) = &c.mirror {
+                        rule.push_str(&format!(",mirror,{target}"));
+                    }
+                    for (key, value) in c.options.hyprland_params() {
+                        rule.push_str(&format!(",{key},{value}"));
+                    }
+                    rule
                 }
             })
             .collect::<Vec<_>>()
@@ -115,23 +202,9 @@ impl DisplayBackend for HyprlandBackend {
         Ok(())
     }
 
-    fn persist(&self, configs: &[MonitorConfig]) -> Result<String, String> {
-        let config_dir =
-            dirs::config_dir().unwrap_or_else(|| std::path::PathBuf::from("~/.config"));
-        let monitors_conf = config_dir.join("hypr").join("monitors.conf");
-
-        let mut content = String::from(
-            "# Generated by disp-center -- edit freely, re-running disp-center will overwrite.\n\n",
-        );
-
-        for c in configs {
-            content.push_str(&c.to_hyprland_line());
-            content.push('\n');
-        }
-
-        std::fs::write(&monitors_conf, &content)
-            .map_err(|e| format!("Failed to write {}: {e}", monitors_conf.display()))?;
-
+    fn persist(&self, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> Result<String, String> {
+        let monitors_conf = monitors_conf_path();
+        hyprconf::write(&monitors_conf, configs, workspaces)?;
         Ok(monitors_conf.display().to_string())
     }
 
@@ -145,30 +218,110 @@ impl DisplayBackend for HyprlandBackend {
         Ok(())
     }
 
-    fn identify(&self, monitors: &[crate::monitor::Monitor]) -> Result<(), String> {
-        // Save the currently focused monitor so we can restore focus after
-        let original_focus = monitors.iter().find(|m| m.focused).map(|m| m.name.clone());
+    fn name(&self) -> &'static str {
+        "Hyprland"
+    }
+
+    fn supports_advanced(&self) -> bool {
+        true
+    }
+
+    fn supports_workspaces(&self) -> bool {
+        true
+    }
+
+    fn workspace_rules(&self) -> Result<Vec<WorkspaceRule>, String> {
+        let json = self.hyprctl(&["workspacerules", "-j"])?;
+        let raw: Vec<HyprWorkspaceRule> = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse workspacerules JSON: {e}"))?;
+        Ok(raw
+            .into_iter()
+            .filter_map(|r| {
+                let workspace: i32 = r.workspace.parse().ok()?;
+                let monitor = r.monitor.filter(|m| !m.is_empty())?;
+                (1..=WORKSPACES)
+                    .contains(&workspace)
+                    .then_some(WorkspaceRule { workspace, monitor })
+            })
+            .collect())
+    }
+
+    fn apply_workspaces(&self, rules: &[WorkspaceRule]) -> Result<(), String> {
+        if rules.is_empty() {
+            return Ok(());
+        }
+        // The rule makes new workspaces open there; the dispatch moves
+        // existing ones.
+        let batch: String = rules
+            .iter()
+            .map(|r| {
+                format!(
+                    "keyword workspace {0},monitor:{1},default:true;dispatch moveworkspacetomonitor {0} {1}",
+                    r.workspace, r.monitor
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        self.hyprctl(&["--batch", &batch])?;
+        Ok(())
+    }
+
+    fn supports_lid_switch(&self) -> bool {
+        true
+    }
 
-        for (i, m) in monitors.iter().enumerate() {
-            // Focus the target monitor so the notification appears on it
-            let _ = self.hyprctl(&["dispatch", "focusmonitor", &m.name]);
-            // Brief pause to let focus settle
-            std::thread::sleep(std::time::Duration::from_millis(50));
-            // Show a large notification with the monitor number and name
-            let msg = format!("fontsize:40 {}  {}", i + 1, m.name);
-            let _ = self.hyprctl(&["notify", "0", "2000", "rgb(89b4fa)", &msg]);
+    /// `Disable` if monitors.conf has our lid bindings, else logind's policy.
+    fn lid_policy(&self) -> LidPolicy {
+        let content = std::fs::read_to_string(monitors_conf_path()).unwrap_or_default();
+        if hyprconf::has_lid_bindings(&content) {
+            LidPolicy::Disable
+        } else {
+            lid::logind_policy()
         }
+    }
 
-        // Restore focus to the originally focused monitor
-        if let Some(orig) = original_focus {
-            std::thread::sleep(std::time::Duration::from_millis(50));
-            let _ = self.hyprctl(&["dispatch", "focusmonitor", &orig]);
+    /// Binds the lid switch live and in monitors.conf, then tells logind
+    /// whether to suspend.
+    fn set_lid_policy(&self, policy: LidPolicy, internal: &MonitorConfig) -> Result<(), String> {
+        let bind = policy == LidPolicy::Disable;
+        hyprconf::write_lid(&monitors_conf_path(), bind.then_some(internal))?;
+
+        // Unbinding a switch that isn't bound is harmless.
+        let mut batch = String::from("keyword unbind , switch:on:Lid Switch;keyword unbind , switch:off:Lid Switch");
+        if bind {
+            for binding in hyprconf::lid_bindings(internal) {
+                let (_, value) = binding.split_once('=').unwrap_or_default();
+                batch.push_str(&format!(";keyword bindl {}", value.trim()));
+            }
         }
+        self.hyprctl(&["--batch", &batch])?;
+        lid::set_logind_policy(policy)
+    }
 
+    /// Hyprland has no event for mode changes; `configreloaded` covers
+    /// edits to monitors.conf.
+    fn watch(&self, changed: Box<dyn Fn() + Send>) -> Result<(), String> {
+        let path = event_socket_path()?;
+        let stream = UnixStream::connect(&path)
+            .map_err(|e| format!("Failed to connect to {}: {e}", path.display()))?;
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                if is_hotplug_event(&line) || line.starts_with("configreloaded>>") {
+                    changed();
+                }
+            }
+        });
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
-        "Hyprland"
+    fn place_overlay(&self, title: &str, x: i32, y: i32) -> Result<(), String> {
+        // The window rules for disp-center-identify already float it.
+        let window = format!("title:^({title})$");
+        let batch = format!(
+            "dispatch setfloating {window};dispatch movewindowpixel exact {x} {y},{window};dispatch pin {window}"
+        );
+        self.hyprctl(&["--batch", &batch])?;
+        Ok(())
     }
 }
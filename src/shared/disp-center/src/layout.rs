@@ -0,0 +1,404 @@
+use crate::monitor::Monitor;
+
+/// Scales are multiples of 1/120 (the wp-fractional-scale unit Hyprland uses).
+const SCALE_STEPS: i32 = 120;
+const MIN_STEPS: i32 = 60; // 0.5
+const MAX_STEPS: i32 = 360; // 3.0
+
+/// Scales between 0.5 and 3.0 that divide `width`x`height` into whole
+/// logical pixels. 1.0 is always included.
+pub fn valid_scales(width: i32, height: i32) -> Vec<f64> {
+    if width <= 0 || height <= 0 {
+        return vec![1.0];
+    }
+    // width / (k / 120) is integral when k divides width * 120.
+    let (w, h) = (width as i64 * SCALE_STEPS as i64, height as i64 * SCALE_STEPS as i64);
+    (MIN_STEPS..=MAX_STEPS)
+        .filter(|&k| w % k as i64 == 0 && h % k as i64 == 0)
+        .map(|k| k as f64 / SCALE_STEPS as f64)
+        .collect()
+}
+
+/// The valid scale for `width`x`height` closest to `scale`.
+pub fn nearest_valid_scale(scale: f64, width: i32, height: i32) -> f64 {
+    valid_scales(width, height)
+        .into_iter()
+        .min_by(|a, b| (a - scale).abs().total_cmp(&(b - scale).abs()))
+        .unwrap_or(1.0)
+}
+
+pub fn is_valid_scale(scale: f64, width: i32, height: i32) -> bool {
+    (nearest_valid_scale(scale, width, height) - scale).abs() < 0.0005
+}
+
+/// Scale for config files and IPC: exact enough for the compositor to land
+/// on the same 1/120 step ("1.333333"), without trailing zeros ("1.5").
+pub fn format_scale(scale: f64) -> String {
+    let s = format!("{scale:.6}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Short label for the scale control, e.g. "1.333".
+pub fn scale_label(scale: f64) -> String {
+    let s = format!("{scale:.3}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// An output's position with its size in physical and logical pixels.
+pub struct Placed {
+    pub x: i32,
+    pub y: i32,
+    pub physical: (i32, i32),
+    pub logical: (i32, i32),
+}
+
+/// Re-place outputs that were arranged edge to edge using physical sizes
+/// so they touch the same way with logical sizes. Outputs keep the
+/// neighbour (or aligned edge) they had; the rest keep their position.
+pub fn physical_to_logical(outputs: &[Placed]) -> Vec<(i32, i32)> {
+    let xs = place_axis(outputs, |p| (p.x, p.physical.0, p.logical.0));
+    let ys = place_axis(outputs, |p| (p.y, p.physical.1, p.logical.1));
+    xs.into_iter().zip(ys).collect()
+}
+
+/// One axis of `physical_to_logical`; `axis` gives (position, physical
+/// length, logical length).
+fn place_axis(outputs: &[Placed], axis: impl Fn(&Placed) -> (i32, i32, i32)) -> Vec<i32> {
+    let spans: Vec<(i32, i32, i32)> = outputs.iter().map(axis).collect();
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| spans[i].0);
+
+    let mut placed: Vec<Option<i32>> = vec![None; spans.len()];
+    for i in order {
+        let (pos, len, log) = spans[i];
+        let done = || (0..spans.len()).filter(|&j| j != i).filter_map(|j| Some((spans[j], placed[j]?)));
+        let new = done()
+            .find(|((p, l, _), _)| p + l == pos) // right after a neighbour
+            .map(|((_, _, lj), nj)| nj + lj)
+            .or_else(|| done().find(|((p, _, _), _)| *p == pos).map(|(_, nj)| nj)) // same start
+            .or_else(|| {
+                done()
+                    .find(|((p, l, _), _)| p + l == pos + len) // same end
+                    .map(|((_, _, lj), nj)| nj + lj - log)
+            })
+            .unwrap_or(pos);
+        placed[i] = Some(new);
+    }
+    placed.into_iter().map(|p| p.unwrap_or(0)).collect()
+}
+
+/// Whether the outputs touch edge to edge without overlapping when sized
+/// with `size`.
+fn is_tight(monitors: &[Monitor], size: impl Fn(&Monitor) -> (i32, i32)) -> bool {
+    let rects: Vec<Rect> = monitors
+        .iter()
+        .filter(|m| m.enabled && m.mirror.is_none())
+        .map(|m| {
+            let (w, h) = size(m);
+            Rect { name: m.name.clone(), x: m.x, y: m.y, w, h }
+        })
+        .collect();
+    let mut group: Vec<usize> = (0..rects.len()).collect();
+    for i in 0..rects.len() {
+        for j in i + 1..rects.len() {
+            let (a, b) = (&rects[i], &rects[j]);
+            let ox = overlap(a.x, a.w, b.x, b.w);
+            let oy = overlap(a.y, a.h, b.y, b.h);
+            if ox > 0 && oy > 0 {
+                return false;
+            }
+            if (ox == 0 && oy > 0) || (oy == 0 && ox > 0) {
+                join(&mut group, i, j);
+            }
+        }
+    }
+    (0..rects.len()).all(|i| find(&mut group, i) == find(&mut group, 0))
+}
+
+/// Layouts written by older versions used physical sizes, which leaves
+/// gaps (or overlaps) around scaled outputs. If the outputs only fit
+/// together with physical sizes, move them to logical positions.
+/// Returns whether anything moved.
+pub fn fix_physical_layout(monitors: &mut [Monitor]) -> bool {
+    if is_tight(monitors, Monitor::logical_size) || !is_tight(monitors, Monitor::rotated_size) {
+        return false;
+    }
+    let placed: Vec<Placed> = monitors
+        .iter()
+        .map(|m| Placed { x: m.x, y: m.y, physical: m.rotated_size(), logical: m.logical_size() })
+        .collect();
+    for (m, (x, y)) in monitors.iter_mut().zip(physical_to_logical(&placed)) {
+        m.x = x;
+        m.y = y;
+    }
+    true
+}
+
+/// Something about the arrangement the user should fix before applying.
+pub struct Problem {
+    pub message: String,
+    /// Connector names of the outputs involved.
+    pub outputs: Vec<String>,
+}
+
+/// An output's area on the desktop in logical pixels.
+struct Rect {
+    name: String,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+}
+
+/// Length of the overlap of [a, a+al) and [b, b+bl); negative for a gap.
+fn overlap(a: i32, al: i32, b: i32, bl: i32) -> i32 {
+    (a + al).min(b + bl) - a.max(b)
+}
+
+/// Check scales and the arrangement of the enabled, non-mirrored outputs:
+/// overlapping outputs, gaps between outputs and groups of outputs that
+/// don't touch the rest (the cursor can't cross between them).
+pub fn problems(monitors: &[Monitor]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for m in monitors.iter().filter(|m| m.enabled) {
+        if !is_valid_scale(m.scale, m.width, m.height) {
+            problems.push(Problem {
+                message: format!(
+                    "{}: scale {} doesn't divide {}x{} evenly",
+                    m.name, scale_label(m.scale), m.width, m.height
+                ),
+                outputs: vec![m.name.clone()],
+            });
+        }
+    }
+
+    let rects: Vec<Rect> = monitors
+        .iter()
+        .filter(|m| m.enabled && m.mirror.is_none())
+        .map(|m| {
+            let (w, h) = m.logical_size();
+            Rect { name: m.name.clone(), x: m.x, y: m.y, w, h }
+        })
+        .collect();
+
+    // Pairs that touch are connected; pairs facing each other across a gap
+    // are candidates for a "gap" warning.
+    let mut group: Vec<usize> = (0..rects.len()).collect();
+    let mut gaps: Vec<(i32, usize, usize)> = Vec::new();
+    for i in 0..rects.len() {
+        for j in i + 1..rects.len() {
+            let (a, b) = (&rects[i], &rects[j]);
+            let ox = overlap(a.x, a.w, b.x, b.w);
+            let oy = overlap(a.y, a.h, b.y, b.h);
+            if ox > 0 && oy > 0 {
+                problems.push(Problem {
+                    message: format!("{} and {} overlap", a.name, b.name),
+                    outputs: vec![a.name.clone(), b.name.clone()],
+                });
+                join(&mut group, i, j);
+            } else if (ox == 0 && oy > 0) || (oy == 0 && ox > 0) {
+                join(&mut group, i, j);
+            } else if oy > 0 || ox > 0 {
+                gaps.push((-ox.min(oy), i, j));
+            }
+        }
+    }
+
+    // Only report the smallest gap between two groups.
+    gaps.sort();
+    for (dist, i, j) in gaps {
+        if find(&mut group, i) != find(&mut group, j) {
+            join(&mut group, i, j);
+            problems.push(Problem {
+                message: format!("{}px gap between {} and {}", dist, rects[i].name, rects[j].name),
+                outputs: vec![rects[i].name.clone(), rects[j].name.clone()],
+            });
+        }
+    }
+
+    // Whatever is still apart doesn't even face another output.
+    let mut roots: Vec<usize> = (0..rects.len()).map(|i| find(&mut group, i)).collect();
+    let main = roots.first().copied();
+    roots.sort();
+    roots.dedup();
+    if roots.len() > 1 {
+        let apart: Vec<String> = (0..rects.len())
+            .filter(|&i| Some(find(&mut group, i)) != main)
+            .map(|i| rects[i].name.clone())
+            .collect();
+        problems.push(Problem {
+            message: format!("{} not connected to the other outputs", apart.join(", ")),
+            outputs: apart,
+        });
+    }
+
+    problems
+}
+
+fn find(group: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while group[root] != root {
+        root = group[root];
+    }
+    group[i] = root;
+    root
+}
+
+fn join(group: &mut [usize], a: usize, b: usize) {
+    let (ra, rb) = (find(group, a), find(group, b));
+    group[rb] = ra;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, width: i32, height: i32, x: i32, y: i32, scale: f64) -> Monitor {
+        Monitor { name: name.into(), width, height, x, y, scale, enabled: true, ..Monitor::default() }
+    }
+
+    fn messages(monitors: &[Monitor]) -> Vec<String> {
+        problems(monitors).into_iter().map(|p| p.message).collect()
+    }
+
+    #[test]
+    fn scales_divide_the_mode_evenly() {
+        let qhd = valid_scales(2560, 1440);
+        assert!(qhd.contains(&1.0));
+        assert!(qhd.iter().any(|s| format_scale(*s) == "1.333333"));
+        assert!(qhd.contains(&1.6));
+        assert!(!valid_scales(1920, 1080).contains(&1.3));
+        assert_eq!(valid_scales(0, 0), [1.0]);
+        assert!(is_valid_scale(1.333333, 2560, 1440));
+        assert!(!is_valid_scale(1.3, 1920, 1080));
+    }
+
+    #[test]
+    fn nearest_scale_and_formatting() {
+        assert_eq!(format_scale(nearest_valid_scale(1.3, 1920, 1080)), "1.333333");
+        assert_eq!(nearest_valid_scale(1.5, 1920, 1080), 1.5);
+        assert_eq!(nearest_valid_scale(9.0, 1920, 1080), 3.0);
+        assert_eq!(format_scale(1.0), "1");
+        assert_eq!(format_scale(1.5), "1.5");
+        assert_eq!(format_scale(2.0), "2");
+        assert_eq!(format_scale(1.0 + 1.0 / 120.0), "1.008333");
+        assert_eq!(scale_label(4.0 / 3.0), "1.333");
+    }
+
+    #[test]
+    fn tight_layout_has_no_problems() {
+        let monitors = [
+            monitor("DP-1", 3840, 2160, 0, 0, 2.0),
+            monitor("eDP-1", 1920, 1080, 1920, 0, 1.0),
+            monitor("HDMI-A-1", 1920, 1080, 0, 1080, 1.0),
+        ];
+        assert!(messages(&monitors).is_empty());
+    }
+
+    #[test]
+    fn reports_bad_scales_and_overlaps() {
+        let monitors = [monitor("DP-1", 1920, 1080, 0, 0, 1.3), monitor("DP-2", 1920, 1080, 1000, 0, 1.0)];
+        assert_eq!(
+            messages(&monitors),
+            ["DP-1: scale 1.3 doesn't divide 1920x1080 evenly", "DP-1 and DP-2 overlap"]
+        );
+        let overlap = &problems(&monitors)[1];
+        assert_eq!(overlap.outputs, ["DP-1", "DP-2"]);
+    }
+
+    #[test]
+    fn reports_the_smallest_gap_between_groups() {
+        let monitors = [
+            monitor("DP-1", 1920, 1080, 0, 0, 1.0),
+            monitor("DP-2", 1920, 1080, 1970, 0, 1.0),
+            monitor("DP-3", 1920, 1080, 0, 1110, 1.0),
+            monitor("DP-4", 1920, 1080, 1970, 1110, 1.0),
+        ];
+        // Once the three gaps before it have joined everything, DP-3 and
+        // DP-4 are in the same group and their gap isn't reported.
+        assert_eq!(
+            messages(&monitors),
+            [
+                "30px gap between DP-1 and DP-3",
+                "30px gap between DP-2 and DP-4",
+                "50px gap between DP-1 and DP-2",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_outputs_that_face_nothing() {
+        let mut monitors = vec![
+            monitor("DP-1", 1920, 1080, 0, 0, 1.0),
+            monitor("DP-2", 1920, 1080, 1920, 0, 1.0),
+            monitor("HDMI-A-1", 1920, 1080, 5000, 3000, 1.0),
+        ];
+        let found = problems(&monitors);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "HDMI-A-1 not connected to the other outputs");
+        assert_eq!(found[0].outputs, ["HDMI-A-1"]);
+
+        // Disabled and mirrored outputs aren't part of the arrangement.
+        monitors[2].mirror = Some("DP-1".into());
+        assert!(problems(&monitors).is_empty());
+        monitors[2].mirror = None;
+        monitors[2].enabled = false;
+        assert!(problems(&monitors).is_empty());
+    }
+
+    fn placed(x: i32, y: i32, physical: (i32, i32), scale: f64) -> Placed {
+        let logical = ((physical.0 as f64 / scale).round() as i32, (physical.1 as f64 / scale).round() as i32);
+        Placed { x, y, physical, logical }
+    }
+
+    #[test]
+    fn side_by_side_outputs_close_up() {
+        let outputs = [placed(0, 0, (3840, 2160), 2.0), placed(3840, 0, (1920, 1080), 1.0)];
+        assert_eq!(physical_to_logical(&outputs), [(0, 0), (1920, 0)]);
+        // Order doesn't matter, only positions.
+        let outputs = [placed(1920, 0, (3840, 2160), 2.0), placed(0, 0, (1920, 1080), 1.0)];
+        assert_eq!(physical_to_logical(&outputs), [(1920, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn stacked_outputs_close_up() {
+        let outputs = [
+            placed(0, 0, (3840, 2160), 2.0),
+            placed(0, 2160, (2560, 1440), 1.25),
+            placed(0, 3600, (1920, 1080), 1.0),
+        ];
+        assert_eq!(physical_to_logical(&outputs), [(0, 0), (0, 1080), (0, 2232)]);
+    }
+
+    #[test]
+    fn end_aligned_outputs_stay_aligned() {
+        // A laptop bottom-aligned to the right of a scaled monitor.
+        let outputs = [placed(0, 0, (3840, 2160), 2.0), placed(3840, 1440, (1280, 720), 1.0)];
+        assert_eq!(physical_to_logical(&outputs), [(0, 0), (1920, 360)]);
+        // Right-aligned under it.
+        let outputs = [placed(0, 0, (3840, 2160), 2.0), placed(2560, 2160, (1280, 720), 1.0)];
+        assert_eq!(physical_to_logical(&outputs), [(0, 0), (640, 1080)]);
+    }
+
+    #[test]
+    fn fixes_layouts_that_only_fit_physically() {
+        let mut monitors = [monitor("DP-1", 3840, 2160, 0, 0, 2.0), monitor("eDP-1", 1920, 1080, 3840, 0, 1.0)];
+        assert!(fix_physical_layout(&mut monitors));
+        assert_eq!((monitors[1].x, monitors[1].y), (1920, 0));
+        // Converted once; a second pass leaves it.
+        assert!(!fix_physical_layout(&mut monitors));
+    }
+
+    #[test]
+    fn leaves_layouts_tight_in_logical_sizes() {
+        let mut monitors = [monitor("DP-1", 3840, 2160, 0, 0, 2.0), monitor("eDP-1", 1920, 1080, 1920, 0, 1.0)];
+        assert!(!fix_physical_layout(&mut monitors));
+        assert_eq!((monitors[1].x, monitors[1].y), (1920, 0));
+
+        // Nor one that doesn't fit either way: the user's gap stays.
+        let mut monitors = [monitor("DP-1", 3840, 2160, 0, 0, 2.0), monitor("eDP-1", 1920, 1080, 5000, 0, 1.0)];
+        assert!(!fix_physical_layout(&mut monitors));
+        assert_eq!(monitors[1].x, 5000);
+    }
+}
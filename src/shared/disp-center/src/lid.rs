@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Drop-in written for the "When lid is closed" setting.
+const LOGIND_DROPIN: &str = "/etc/systemd/logind.conf.d/50-disp-center-lid.conf";
+
+/// logind reads its main config, then the drop-ins of all these
+/// directories in file name order.
+const LOGIND_CONF: &[&str] = &["/usr/lib/systemd/logind.conf", "/etc/systemd/logind.conf"];
+const LOGIND_DROPIN_DIRS: &[&str] =
+    &["/usr/lib/systemd/logind.conf.d", "/run/systemd/logind.conf.d", "/etc/systemd/logind.conf.d"];
+
+/// What happens when the lid closes while an external monitor is connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LidPolicy {
+    /// Leave the built-in display on (logind's default when docked).
+    #[default]
+    Ignore,
+    /// Turn the built-in display off; the compositor turns it back on when
+    /// the lid opens.
+    Disable,
+    /// Suspend even though an external monitor is connected.
+    Suspend,
+}
+
+impl LidPolicy {
+    pub const ALL: [LidPolicy; 3] = [LidPolicy::Ignore, LidPolicy::Disable, LidPolicy::Suspend];
+
+    /// Label for the panel's dropdown.
+    pub fn label(self) -> &'static str {
+        match self {
+            LidPolicy::Ignore => "Do nothing",
+            LidPolicy::Disable => "Turn off display",
+            LidPolicy::Suspend => "Suspend",
+        }
+    }
+
+    /// logind's `HandleLidSwitchDocked` action. The compositor handles
+    /// `Disable`, so logind must leave the system running.
+    fn logind_action(self) -> &'static str {
+        match self {
+            LidPolicy::Suspend => "suspend",
+            LidPolicy::Ignore | LidPolicy::Disable => "ignore",
+        }
+    }
+}
+
+/// The policy as far as logind is concerned: `Suspend` if a docked lid
+/// close sleeps the machine, `Ignore` otherwise. Backends that bind the lid
+/// switch report `Disable` themselves.
+pub fn logind_policy() -> LidPolicy {
+    match docked_action().as_str() {
+        "suspend" | "hibernate" | "hybrid-sleep" | "suspend-then-hibernate" => LidPolicy::Suspend,
+        _ => LidPolicy::Ignore,
+    }
+}
+
+/// Make logind's docked lid action match `policy`. Installs the drop-in
+/// with pkexec, so this asks for a password; nothing happens if logind
+/// already does the right thing.
+pub fn set_logind_policy(policy: LidPolicy) -> Result<(), String> {
+    let action = policy.logind_action();
+    if docked_action() == action {
+        return Ok(());
+    }
+
+    let content = format!(
+        "# Written by disp-center (\"When lid is closed\"). What logind does when\n\
+         # the lid closes while an external monitor is connected.\n\
+         [Login]\n\
+         HandleLidSwitchDocked={action}\n"
+    );
+    let staged = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("disp-center")
+        .join("logind-lid.conf");
+    if let Some(dir) = staged.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    std::fs::write(&staged, content).map_err(|e| format!("Failed to write {}: {e}", staged.display()))?;
+
+    // Older logind can't reload; the drop-in then applies from the next boot.
+    let status = Command::new("pkexec")
+        .args([
+            "sh",
+            "-c",
+            r#"install -Dm644 "$1" "$2" && { systemctl reload systemd-logind 2>/dev/null || true; }"#,
+            "sh",
+        ])
+        .arg(&staged)
+        .arg(LOGIND_DROPIN)
+        .status()
+        .map_err(|e| format!("Failed to run pkexec: {e}"))?;
+    let _ = std::fs::remove_file(&staged);
+    if !status.success() {
+        return Err(format!("Installing {LOGIND_DROPIN} failed or was cancelled"));
+    }
+    Ok(())
+}
+
+/// Effective `HandleLidSwitchDocked` (logind's default is "ignore").
+fn docked_action() -> String {
+    let mut dropins: Vec<PathBuf> = LOGIND_DROPIN_DIRS
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "conf"))
+        .collect();
+    dropins.sort_by_key(|p| p.file_name().map(|n| n.to_os_string()));
+
+    let mut action = "ignore".to_string();
+    for path in LOGIND_CONF.iter().map(Path::new).chain(dropins.iter().map(PathBuf::as_path)) {
+        let Ok(content) = std::fs::read_to_string(path) else { continue };
+        for line in content.lines() {
+            if let Some(value) = line.trim().strip_prefix("HandleLidSwitchDocked=") {
+                action = value.trim().to_string();
+            }
+        }
+    }
+    action
+}
@@ -1,12 +1,29 @@
 mod backend;
+mod cli;
+mod cvt;
+mod daemon;
 mod demo;
+mod edid;
+mod history;
+mod hyprconf;
 mod hyprland;
+mod layout;
+mod lid;
 mod monitor;
+mod profile;
+mod sway;
 mod theme;
+mod xrandr;
 
 use backend::DisplayBackend;
-use monitor::{canvas_scale_factor, snap_to_nearest_edge, Monitor, MonitorConfig};
+use history::History;
+use lid::LidPolicy;
+use monitor::{
+    canvas_scale_factor, snap_to_nearest_edge, Monitor, MonitorConfig, MonitorMode, WorkspaceRule,
+    COLOR_MODES, WORKSPACES,
+};
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 slint::include_modules!();
@@ -14,6 +31,33 @@ slint::include_modules!();
 const CANVAS_W: f64 = 640.0;
 const CANVAS_H: f64 = 220.0;
 
+/// Seconds to confirm an applied layout before it is rolled back.
+const CONFIRM_SECS: i32 = 15;
+
+/// Wait this long after the last output event before re-querying, so a
+/// dock bringing up several outputs refreshes once.
+const HOTPLUG_SETTLE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Seconds the identify overlays stay up, and their size (see app.slint).
+const IDENTIFY_SECS: u64 = 3;
+const OVERLAY_W: i32 = 360;
+const OVERLAY_H: i32 = 220;
+
+/// What undo and redo bring back: the edited monitors, workspace
+/// assignments and primary monitor.
+struct Snapshot {
+    monitors: Vec<Monitor>,
+    workspaces: Vec<String>,
+    primary: String,
+}
+
+impl Snapshot {
+    fn differs(&self, other: &Snapshot) -> bool {
+        self.primary != other.primary
+            || layout_differs(&self.monitors, &self.workspaces, &other.monitors, &other.workspaces)
+    }
+}
+
 /// State shared between Slint callbacks.
 struct AppState {
     backend: Box<dyn DisplayBackend>,
@@ -23,6 +67,15 @@ struct AppState {
     offset_y: f64,
     scale: f64,
     primary: String,
+    original_primary: String,
+    /// Monitor of workspace N at index N-1; empty = not assigned.
+    workspaces: Vec<String>,
+    original_workspaces: Vec<String>,
+    /// Takes effect when changed, not on apply.
+    lid_policy: LidPolicy,
+    history: History<Snapshot>,
+    /// Where kept layouts are saved as profiles.
+    profiles: PathBuf,
 }
 
 impl AppState {
@@ -35,112 +88,498 @@ impl AppState {
             offset_y: 0.0,
             scale: 0.1,
             primary: String::new(),
+            original_primary: String::new(),
+            workspaces: vec![String::new(); WORKSPACES as usize],
+            original_workspaces: vec![String::new(); WORKSPACES as usize],
+            lid_policy: LidPolicy::Ignore,
+            history: History::new(),
+            profiles: profile::profiles_path(),
         }
     }
 
-    fn load_monitors(&mut self) -> Result<(), String> {
+    /// Query the backend. Returns whether a layout arranged in physical
+    /// pixels was moved to logical positions (an unapplied change).
+    fn load_monitors(&mut self) -> Result<bool, String> {
         self.monitors = self.backend.query_monitors()?;
         self.original = self.monitors.clone();
-        self.primary = self
-            .monitors
+        let converted = layout::fix_physical_layout(&mut self.monitors);
+        self.workspaces = self.query_workspaces();
+        self.original_workspaces = self.workspaces.clone();
+        self.primary = self.focused_monitor();
+        self.original_primary = self.primary.clone();
+        self.lid_policy = self.backend.lid_policy();
+        self.history.clear();
+        self.recalc_canvas();
+        Ok(converted)
+    }
+
+    /// Re-query after outputs were added, removed or reconfigured. Monitors
+    /// with unsaved edits keep them (with fresh modes and EDID); the rest
+    /// take the compositor's state. Returns the connectors added and removed.
+    fn merge_live(&mut self) -> Result<(Vec<String>, Vec<String>), String> {
+        let fresh = self.backend.query_monitors()?;
+        let connected = |name: &str| fresh.iter().any(|f| f.name == name);
+        let added: Vec<String> = fresh
+            .iter()
+            .filter(|f| !self.monitors.iter().any(|m| m.name == f.name))
+            .map(|f| f.name.clone())
+            .collect();
+        let removed: Vec<String> =
+            self.monitors.iter().filter(|m| !connected(&m.name)).map(|m| m.name.clone()).collect();
+
+        let monitors = fresh
+            .iter()
+            .map(|f| {
+                let edited = self.monitors.iter().find(|m| m.name == f.name).filter(|m| {
+                    self.original.iter().find(|o| o.name == m.name).is_some_and(|o| settings_differ(m, o))
+                });
+                let Some(m) = edited else { return f.clone() };
+                let mut kept = m.clone();
+                kept.id = f.id;
+                kept.description = f.description.clone();
+                kept.dpms = f.dpms;
+                kept.focused = f.focused;
+                kept.available_modes = f.available_modes.clone();
+                kept.edid = f.edid.clone();
+                if kept.mirror.as_deref().is_some_and(|t| !connected(t)) {
+                    kept.mirror = None;
+                }
+                kept
+            })
+            .collect();
+
+        let fresh_workspaces = self.query_workspaces();
+        for (i, slot) in self.workspaces.iter_mut().enumerate() {
+            if *slot == self.original_workspaces[i] {
+                *slot = fresh_workspaces[i].clone();
+            }
+        }
+        self.original_workspaces = fresh_workspaces;
+        self.original = fresh;
+        self.monitors = monitors;
+        // Snapshots of a different set of outputs can't be restored.
+        if !added.is_empty() || !removed.is_empty() {
+            self.history.clear();
+        }
+        if !self.monitors.iter().any(|m| m.name == self.primary) {
+            self.primary = self.focused_monitor();
+        }
+        if !self.monitors.iter().any(|m| m.name == self.original_primary) {
+            self.original_primary = self.primary.clone();
+        }
+        self.recalc_canvas();
+        Ok((added, removed))
+    }
+
+    /// Monitor of each workspace slot as the backend reports it.
+    fn query_workspaces(&self) -> Vec<String> {
+        let mut slots = vec![String::new(); WORKSPACES as usize];
+        for rule in self.backend.workspace_rules().unwrap_or_default() {
+            if let Some(slot) = slots.get_mut((rule.workspace - 1) as usize) {
+                *slot = rule.monitor;
+            }
+        }
+        slots
+    }
+
+    fn focused_monitor(&self) -> String {
+        self.monitors
             .iter()
             .find(|m| m.focused)
             .or(self.monitors.first())
             .map(|m| m.name.clone())
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
+
+    /// Change monitor `idx` in a way that can change its logical size
+    /// (mode, scale, rotation). Outputs right of or below it move along so
+    /// neighbours keep touching.
+    fn resize(&mut self, idx: usize, change: impl FnOnce(&mut Monitor)) {
+        let Some(m) = self.monitors.get_mut(idx) else { return };
+        let (x, y) = (m.x, m.y);
+        let (old_w, old_h) = m.logical_size();
+        change(m);
+        let (new_w, new_h) = m.logical_size();
+        if m.mirror.is_none() {
+            for (i, o) in self.monitors.iter_mut().enumerate() {
+                if i == idx || o.mirror.is_some() {
+                    continue;
+                }
+                if o.x >= x + old_w {
+                    o.x += new_w - old_w;
+                }
+                if o.y >= y + old_h {
+                    o.y += new_h - old_h;
+                }
+            }
+        }
         self.recalc_canvas();
-        Ok(())
     }
 
     fn recalc_canvas(&mut self) {
         self.scale = canvas_scale_factor(&self.monitors, CANVAS_W, CANVAS_H);
-        let min_x = self.monitors.iter().map(|m| m.x).min().unwrap_or(0) as f64;
-        let min_y = self.monitors.iter().map(|m| m.y).min().unwrap_or(0) as f64;
+        let placed = || self.monitors.iter().filter(|m| m.mirror.is_none());
+        let min_x = placed().map(|m| m.x).min().unwrap_or(0) as f64;
+        let min_y = placed().map(|m| m.y).min().unwrap_or(0) as f64;
         self.offset_x = -min_x;
         self.offset_y = -min_y;
     }
 
     fn to_slint_model(&self) -> Vec<MonitorInfo> {
         let margin = 20.0;
+        let problems = layout::problems(&self.monitors);
+        // The lid policy only applies with an external monitor connected.
+        let docked = self.monitors.iter().any(|m| m.enabled && !edid::is_builtin(&m.name));
         self.monitors
             .iter()
             .map(|m| {
-                let modes: Vec<slint::SharedString> = m
-                    .available_modes
-                    .iter()
-                    .map(|mode| slint::SharedString::from(mode.label()))
-                    .collect();
+                let (modes, cur_mode_idx) = mode_options(m);
 
-                let cur_mode_idx = m
-                    .available_modes
-                    .iter()
-                    .position(|mode| {
-                        mode.width == m.width
-                            && mode.height == m.height
-                            && (mode.refresh_rate - m.refresh_rate).abs() < 1.0
-                    })
-                    .unwrap_or(0) as i32;
+                let (w, h) = m.logical_size();
+
+                // Mirrored monitors are drawn stacked on top of their target.
+                let target = m
+                    .mirror
+                    .as_ref()
+                    .and_then(|t| self.monitors.iter().find(|o| &o.name == t));
+                let (stack, anchor) = match target {
+                    Some(t) => (8.0, t),
+                    None => (0.0, m),
+                };
+                let builtin = edid::is_builtin(&m.name);
+                let lid = docked && builtin;
 
                 MonitorInfo {
                     id: m.id,
                     name: slint::SharedString::from(&m.name),
                     description: slint::SharedString::from(&m.description),
+                    details: slint::SharedString::from(monitor_details(m)),
                     width: m.width,
                     height: m.height,
                     refresh_rate: m.refresh_rate as f32,
                     pos_x: m.x,
                     pos_y: m.y,
                     scale: m.scale as f32,
+                    transform: m.transform,
                     enabled: m.enabled,
                     is_primary: m.name == self.primary,
-                    canvas_x: ((m.x as f64 + self.offset_x) * self.scale + margin) as f32,
-                    canvas_y: ((m.y as f64 + self.offset_y) * self.scale + margin) as f32,
-                    canvas_w: (m.width as f64 * self.scale) as f32,
-                    canvas_h: (m.height as f64 * self.scale) as f32,
+                    mirror: slint::SharedString::from(m.mirror.clone().unwrap_or_default()),
+                    canvas_x: ((anchor.x as f64 + self.offset_x) * self.scale + margin + stack) as f32,
+                    canvas_y: ((anchor.y as f64 + self.offset_y) * self.scale + margin + stack) as f32,
+                    canvas_w: (w as f64 * self.scale) as f32,
+                    canvas_h: (h as f64 * self.scale) as f32,
                     available_modes: slint::ModelRc::new(slint::VecModel::from(modes)),
                     current_mode_index: cur_mode_idx,
+                    warning: problems.iter().any(|p| p.outputs.contains(&m.name)),
+                    builtin,
+                    lid_note: slint::SharedString::from(match self.lid_policy {
+                        LidPolicy::Disable if lid => "Off when lid is closed",
+                        LidPolicy::Suspend if lid => "Suspends when lid is closed",
+                        _ => "",
+                    }),
+                    lid_off: lid && self.lid_policy == LidPolicy::Disable,
                 }
             })
             .collect()
     }
 
     fn configs_from_current(&self) -> Vec<MonitorConfig> {
+        self.monitors.iter().map(MonitorConfig::from_monitor).collect()
+    }
+
+    /// Monitors `idx` can mirror: the other enabled, non-mirrored ones.
+    fn mirror_targets(&self, idx: usize) -> Vec<String> {
         self.monitors
             .iter()
-            .map(|m| MonitorConfig {
-                name: m.name.clone(),
-                width: m.width,
-                height: m.height,
-                refresh_rate: m.refresh_rate,
-                x: m.x,
-                y: m.y,
-                scale: m.scale,
-                enabled: m.enabled,
-            })
+            .enumerate()
+            .filter(|(i, m)| *i != idx && m.enabled && m.mirror.is_none())
+            .map(|(_, m)| m.name.clone())
+            .collect()
+    }
+
+    /// "Mirror of" dropdown entries for monitor `idx` and the selected one.
+    fn mirror_options(&self, idx: usize) -> (Vec<slint::SharedString>, i32) {
+        let targets = self.mirror_targets(idx);
+        let current = self.monitors[idx]
+            .mirror
+            .as_ref()
+            .and_then(|t| targets.iter().position(|n| n == t))
+            .map_or(0, |i| i as i32 + 1);
+        let mut options = vec![slint::SharedString::from("None")];
+        options.extend(targets.iter().map(slint::SharedString::from));
+        (options, current)
+    }
+
+    /// Lid policies the backend can carry out, in dropdown order.
+    fn lid_policies(&self) -> Vec<LidPolicy> {
+        let switch = self.backend.supports_lid_switch();
+        LidPolicy::ALL.into_iter().filter(|p| switch || *p != LidPolicy::Disable).collect()
+    }
+
+    /// Config of the built-in display to restore when the lid opens: its
+    /// live settings, switched on if it is off now.
+    fn internal_config(&self) -> Option<MonitorConfig> {
+        let m = self.original.iter().find(|m| edid::is_builtin(&m.name))?;
+        Some(MonitorConfig { enabled: true, mirror: None, ..MonitorConfig::from_monitor(m) })
+    }
+
+    fn workspace_rules(&self) -> Vec<WorkspaceRule> {
+        self.workspaces
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| !m.is_empty())
+            .map(|(i, m)| WorkspaceRule { workspace: i as i32 + 1, monitor: m.clone() })
             .collect()
     }
 
+    /// Move workspace `idx` (0-based) to the next placed monitor, then back
+    /// to "not assigned".
+    fn cycle_workspace(&mut self, idx: usize) {
+        let mut choices = vec![String::new()];
+        choices.extend(
+            self.monitors
+                .iter()
+                .filter(|m| m.enabled && m.mirror.is_none())
+                .map(|m| m.name.clone()),
+        );
+        let Some(current) = self.workspaces.get_mut(idx) else { return };
+        let next = choices.iter().position(|c| c == current).map_or(0, |i| (i + 1) % choices.len());
+        *current = choices[next].clone();
+    }
+
     fn has_changes(&self) -> bool {
-        if self.monitors.len() != self.original.len() {
-            return true;
+        self.snapshot().differs(&self.original_snapshot())
+    }
+
+    /// Apply the edited layout live, before the user confirms it. Err is
+    /// the status when nothing was applied; a failed workspace move or
+    /// primary switch keeps the apply and is returned as a warning.
+    fn apply_live(&self) -> Result<Option<String>, String> {
+        self.backend.apply(&self.configs_from_current()).map_err(|e| format!("Apply failed: {e}"))?;
+        if let Err(e) = self.backend.apply_workspaces(&self.workspace_rules()) {
+            return Ok(Some(format!("Moving workspaces failed: {e}")));
         }
-        for (m, o) in self.monitors.iter().zip(self.original.iter()) {
-            if m.x != o.x
-                || m.y != o.y
-                || m.width != o.width
-                || m.height != o.height
-                || (m.refresh_rate - o.refresh_rate).abs() > 0.1
-                || (m.scale - o.scale).abs() > 0.01
-                || m.enabled != o.enabled
-            {
-                return true;
-            }
+        Ok(self.apply_primary().err().map(|e| format!("Setting the primary monitor failed: {e}")))
+    }
+
+    /// Tell the backend about a changed primary monitor.
+    fn apply_primary(&self) -> Result<(), String> {
+        if self.primary == self.original_primary || self.primary.is_empty() {
+            return Ok(());
         }
-        false
+        self.backend.set_primary(&self.primary)
     }
+
+    /// Keep the applied layout: save it to the config file and as the
+    /// profile for this monitor set. Returns the status to show.
+    fn keep_applied(&mut self) -> String {
+        let configs = self.configs_from_current();
+        let workspaces = self.workspace_rules();
+
+        // Remember the layout for this monitor set so the daemon
+        // can restore it on the next hotplug.
+        let mut profiles = profile::load(&self.profiles);
+        let name = profile::Profile::default_name(&self.monitors);
+        profile::upsert(
+            &mut profiles,
+            profile::Profile::from_monitors(&name, &self.monitors, &self.primary, &workspaces),
+        );
+        if let Err(e) = profile::save(&self.profiles, &profiles) {
+            eprintln!("Failed to save profile: {e}");
+        }
+
+        // Slots the user cleared are saved as unassigned; the config's
+        // other workspace rules are left alone.
+        let mut persisted = workspaces.clone();
+        persisted.extend(
+            self.workspaces
+                .iter()
+                .zip(&self.original_workspaces)
+                .enumerate()
+                .filter(|(_, (now, before))| now.is_empty() && !before.is_empty())
+                .map(|(i, _)| WorkspaceRule { workspace: i as i32 + 1, monitor: String::new() }),
+        );
+        let status = match self.backend.persist(&configs, &persisted) {
+            Ok(path) => format!("Applied and saved to {path}"),
+            Err(e) => format!("Applied live but failed to save: {e}"),
+        };
+        self.original = self.monitors.clone();
+        self.original_workspaces = self.workspaces.clone();
+        self.original_primary = self.primary.clone();
+        status
+    }
+
+    /// Apply the layout from before the apply again (rejected or timed
+    /// out). Returns the status to show.
+    fn roll_back(&mut self) -> String {
+        // Undo brings the rejected layout back for another try.
+        let rejected = self.primary.clone();
+        self.reset_to("roll back", self.original_snapshot());
+        let result = self
+            .backend
+            .apply(&self.configs_from_current())
+            .and_then(|()| self.backend.apply_workspaces(&self.workspace_rules()))
+            .and_then(|()| {
+                if rejected == self.primary || self.primary.is_empty() {
+                    return Ok(());
+                }
+                self.backend.set_primary(&self.primary)
+            });
+        match result {
+            Ok(()) => "Reverted to previous settings".into(),
+            Err(e) => format!("Revert failed: {e}"),
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            monitors: self.monitors.clone(),
+            workspaces: self.workspaces.clone(),
+            primary: self.primary.clone(),
+        }
+    }
+
+    /// The layout as last applied (or loaded).
+    fn original_snapshot(&self) -> Snapshot {
+        Snapshot {
+            monitors: self.original.clone(),
+            workspaces: self.original_workspaces.clone(),
+            primary: self.original_primary.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.monitors = snapshot.monitors;
+        self.workspaces = snapshot.workspaces;
+        self.primary = snapshot.primary;
+        self.recalc_canvas();
+    }
+
+    /// Make an undoable edit; nothing is recorded if it changed nothing.
+    /// Edits in the same `group` right after each other (slider drags)
+    /// become one step.
+    fn edit(&mut self, group: Option<&str>, change: impl FnOnce(&mut Self)) {
+        let before = self.snapshot();
+        change(self);
+        let after = self.snapshot();
+        if !after.differs(&before) {
+            return;
+        }
+        let base = group.and_then(|g| self.history.merging(g)).unwrap_or(&before);
+        let description = describe_edit(base, &after);
+        self.history.record(description, before, group);
+    }
+
+    /// Replace the layout with `snapshot` as one undoable step.
+    fn reset_to(&mut self, description: &str, snapshot: Snapshot) {
+        let before = self.snapshot();
+        self.restore(snapshot);
+        if self.snapshot().differs(&before) {
+            self.history.record(description, before, None);
+        }
+    }
+
+    /// Undo the last edit; returns its description.
+    fn undo(&mut self) -> Option<String> {
+        let (description, before) = self.history.undo(self.snapshot())?;
+        self.restore(before);
+        Some(description)
+    }
+
+    /// Redo the last undone edit; returns its description.
+    fn redo(&mut self) -> Option<String> {
+        let (description, after) = self.history.redo(self.snapshot())?;
+        self.restore(after);
+        Some(description)
+    }
+}
+
+/// Whether two layouts differ in anything the user can change.
+fn layout_differs(
+    monitors: &[Monitor],
+    workspaces: &[String],
+    other: &[Monitor],
+    other_workspaces: &[String],
+) -> bool {
+    workspaces != other_workspaces
+        || monitors.len() != other.len()
+        || monitors.iter().zip(other).any(|(m, o)| settings_differ(m, o))
 }
 
-fn apply_theme(ui: &App) {
+/// "moved HDMI-A-1", "DP-1 scale 1.5 → 1.25": what changed from `before`
+/// to `after`. Outputs that only moved because a neighbour was resized
+/// aren't mentioned.
+fn describe_edit(before: &Snapshot, after: &Snapshot) -> String {
+    let mut changes = Vec::new();
+    let mut moves = Vec::new();
+    for m in &after.monitors {
+        let Some(o) = before.monitors.iter().find(|o| o.name == m.name) else { continue };
+        let name = &m.name;
+        if m.enabled != o.enabled {
+            changes.push(format!("{} {name}", if m.enabled { "enabled" } else { "disabled" }));
+        } else if m.width != o.width || m.height != o.height || (m.refresh_rate - o.refresh_rate).abs() > 0.1 {
+            let mode = |m: &Monitor| format!("{}x{}@{:.0}Hz", m.width, m.height, m.refresh_rate);
+            changes.push(format!("{name} {} \u{2192} {}", mode(o), mode(m)));
+        } else if (m.scale - o.scale).abs() > 0.01 {
+            changes.push(format!(
+                "{name} scale {} \u{2192} {}",
+                layout::scale_label(o.scale),
+                layout::scale_label(m.scale)
+            ));
+        } else if m.transform != o.transform {
+            changes.push(format!("rotated {name}"));
+        } else if m.mirror != o.mirror {
+            changes.push(match &m.mirror {
+                Some(target) => format!("{name} mirrors {target}"),
+                None => format!("{name} stops mirroring"),
+            });
+        } else if m.options != o.options {
+            changes.push(format!("{name} advanced settings"));
+        } else if m.x != o.x || m.y != o.y {
+            moves.push(format!("moved {name}"));
+        }
+    }
+    if after.primary != before.primary {
+        changes.push(format!("primary \u{2192} {}", after.primary));
+    }
+    for (i, (new, old)) in after.workspaces.iter().zip(&before.workspaces).enumerate() {
+        if new != old {
+            changes.push(match new.as_str() {
+                "" => format!("workspace {} unassigned", i + 1),
+                monitor => format!("workspace {} \u{2192} {monitor}", i + 1),
+            });
+        }
+    }
+    if changes.is_empty() {
+        changes = moves;
+    }
+    match changes.len() {
+        0 => "layout change".into(),
+        1 => changes.remove(0),
+        n => format!("{} (+{} more)", changes[0], n - 1),
+    }
+}
+
+/// Whether `m` has settings the user can change that differ from `o`.
+fn settings_differ(m: &Monitor, o: &Monitor) -> bool {
+    m.x != o.x
+        || m.y != o.y
+        || m.width != o.width
+        || m.height != o.height
+        || (m.refresh_rate - o.refresh_rate).abs() > 0.1
+        || (m.scale - o.scale).abs() > 0.01
+        || m.transform != o.transform
+        || m.mirror != o.mirror
+        || m.options != o.options
+        || m.enabled != o.enabled
+        || m.custom_mode != o.custom_mode
+}
+
+fn apply_theme<C>(ui: &C)
+where
+    C: slint::ComponentHandle,
+    for<'a> Theme<'a>: slint::Global<'a, C>,
+{
     let palette = theme::load_theme_from_eww_scss(&format!(
         "{}/.config/eww/theme-colors.scss",
         std::env::var("HOME").unwrap_or_default()
@@ -160,55 +599,251 @@ fn apply_theme(ui: &App) {
     theme.set_opacity(palette.opacity);
 }
 
+/// An identify window and where it should go in layout coordinates.
+struct Overlay {
+    window: IdentifyOverlay,
+    title: String,
+    x: i32,
+    y: i32,
+}
+
+/// Open an overlay with the number, connector and model on every enabled,
+/// non-mirrored output.
+fn open_identify_overlays(monitors: &[Monitor]) -> Result<Vec<Overlay>, String> {
+    let mut overlays = Vec::new();
+    for (i, m) in monitors.iter().enumerate().filter(|(_, m)| m.enabled && m.mirror.is_none()) {
+        let window = IdentifyOverlay::new().map_err(|e| format!("Failed to create overlay: {e}"))?;
+        apply_theme(&window);
+        window.set_number(i as i32 + 1);
+        window.set_name(slint::SharedString::from(m.name.as_str()));
+        window.set_model(slint::SharedString::from(m.description.as_str()));
+
+        let (w, h) = m.logical_size();
+        let (x, y) = (m.x + (w - OVERLAY_W) / 2, m.y + (h - OVERLAY_H) / 2);
+        // Honoured on X11, where layout coordinates are physical pixels;
+        // Wayland compositors place the window through the backend.
+        window.window().set_position(slint::PhysicalPosition::new(x, y));
+        window.show().map_err(|e| format!("Failed to show overlay: {e}"))?;
+
+        overlays.push(Overlay { window, title: format!("disp-center identify {}", m.name), x, y });
+    }
+    Ok(overlays)
+}
+
 fn push_state_to_ui(ui: &App, state: &AppState) {
     let model = state.to_slint_model();
     let model_rc = slint::ModelRc::new(slint::VecModel::from(model));
     ui.set_monitors(model_rc);
     ui.set_has_changes(state.has_changes());
+    ui.set_undo_text(slint::SharedString::from(state.history.undo_description().unwrap_or_default()));
+    ui.set_redo_text(slint::SharedString::from(state.history.redo_description().unwrap_or_default()));
+    let workspaces: Vec<slint::SharedString> =
+        state.workspaces.iter().map(slint::SharedString::from).collect();
+    ui.set_workspace_monitors(slint::ModelRc::new(slint::VecModel::from(workspaces)));
+    let warnings: Vec<String> =
+        layout::problems(&state.monitors).into_iter().map(|p| p.message).collect();
+    ui.set_layout_warnings(slint::SharedString::from(warnings.join("  \u{b7}  ")));
+    let policies = state.lid_policies();
+    ui.set_lid_policy_names(slint::ModelRc::new(slint::VecModel::from(
+        policies.iter().map(|p| slint::SharedString::from(p.label())).collect::<Vec<_>>(),
+    )));
+    ui.set_lid_policy(policies.iter().position(|p| *p == state.lid_policy).unwrap_or(0) as i32);
 
     let idx = ui.get_selected_index();
     if idx >= 0 && (idx as usize) < state.monitors.len() {
         let m = &state.monitors[idx as usize];
-        let modes: Vec<slint::SharedString> = m
-            .available_modes
-            .iter()
-            .map(|mode| slint::SharedString::from(mode.label()))
-            .collect();
+        let (modes, mode_idx) = mode_options(m);
         ui.set_selected_modes(slint::ModelRc::new(slint::VecModel::from(modes)));
+        ui.set_selected_mode_index(mode_idx);
+        push_scale_to_ui(ui, m);
+        ui.set_selected_transform(m.transform);
 
-        let mode_idx = m
-            .available_modes
-            .iter()
-            .position(|mode| {
-                mode.width == m.width
-                    && mode.height == m.height
-                    && (mode.refresh_rate - m.refresh_rate).abs() < 1.0
-            })
-            .unwrap_or(0);
-        ui.set_selected_mode_index(mode_idx as i32);
-        ui.set_selected_scale(m.scale as f32);
+        let (options, current) = state.mirror_options(idx as usize);
+        ui.set_mirror_options(slint::ModelRc::new(slint::VecModel::from(options)));
+        ui.set_selected_mirror_index(current);
+        push_advanced_to_ui(ui, m);
+    }
+}
+
+/// Resolution dropdown entries and the current one: the listed modes, the
+/// current mode if it isn't one of them (custom timings), then the entry
+/// that opens the "Custom mode" dialog (always last, see app.slint).
+fn mode_options(m: &Monitor) -> (Vec<slint::SharedString>, i32) {
+    let mut labels: Vec<slint::SharedString> =
+        m.available_modes.iter().map(|mode| slint::SharedString::from(mode.label())).collect();
+    let listed = m.available_modes.iter().position(|mode| {
+        mode.width == m.width && mode.height == m.height && (mode.refresh_rate - m.refresh_rate).abs() < 1.0
+    });
+    let current = match listed.filter(|_| m.custom_mode.is_none()) {
+        Some(i) => i,
+        None if m.width > 0 => {
+            let mode = MonitorMode { width: m.width, height: m.height, refresh_rate: m.refresh_rate };
+            labels.push(slint::SharedString::from(format!("{} (custom)", mode.label())));
+            labels.len() - 1
+        }
+        None => 0,
+    };
+    labels.push(slint::SharedString::from("Custom mode\u{2026}"));
+    (labels, current as i32)
+}
+
+/// Timings from the "Custom mode" dialog's fields.
+fn parse_custom_mode(width: &str, height: &str, refresh: &str, reduced: bool) -> Result<cvt::Modeline, String> {
+    let number = |field: &str, what: &str| {
+        field.trim().parse::<f64>().map_err(|_| format!("{what} must be a number"))
+    };
+    let width = number(width, "Width")? as i32;
+    let height = number(height, "Height")? as i32;
+    cvt::generate(width, height, number(refresh, "Refresh rate")?, reduced)
+}
+
+/// "Dell U2720Q  ·  27.0"  ·  163 DPI" from the EDID, or the compositor's
+/// description without one.
+fn monitor_details(m: &Monitor) -> String {
+    let Some(edid) = &m.edid else {
+        return m.description.clone();
+    };
+    let mut parts = vec![edid.model()];
+    if let Some(inches) = edid.diagonal_inches() {
+        parts.push(format!("{inches:.1}\""));
+    }
+    if let Some(dpi) = edid.dpi(m.width) {
+        parts.push(format!("{dpi:.0} DPI"));
     }
+    parts.join("  \u{b7}  ")
+}
+
+/// Scale control: only scales that give whole logical pixels for the mode.
+fn push_scale_to_ui(ui: &App, m: &Monitor) {
+    let scales = layout::valid_scales(m.width, m.height);
+    let current = scales
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (*a - m.scale).abs().total_cmp(&(*b - m.scale).abs()))
+        .map_or(0, |(i, _)| i);
+    let labels: Vec<slint::SharedString> =
+        scales.iter().map(|s| slint::SharedString::from(layout::scale_label(*s))).collect();
+    ui.set_scale_options(slint::ModelRc::new(slint::VecModel::from(labels)));
+    ui.set_selected_scale_index(current as i32);
+    let recommended = m
+        .recommended_scale()
+        .and_then(|r| scales.iter().position(|s| (s - r).abs() < 0.0005));
+    ui.set_recommended_scale_index(recommended.map_or(-1, |i| i as i32));
+}
+
+fn push_advanced_to_ui(ui: &App, m: &Monitor) {
+    ui.set_selected_vrr(m.options.vrr);
+    ui.set_selected_ten_bit(m.options.ten_bit);
+    ui.set_selected_color_mode(
+        COLOR_MODES.iter().position(|c| *c == m.options.color_mode).unwrap_or(0) as i32,
+    );
+    ui.set_selected_sdr_brightness(m.options.sdr_brightness as f32);
+}
+
+/// Merge a hotplug into the window, keeping the selection and unsaved edits.
+fn refresh_live(ui: &App, st: &mut AppState) {
+    let selected = usize::try_from(ui.get_selected_index())
+        .ok()
+        .and_then(|i| st.monitors.get(i))
+        .map(|m| m.name.clone());
+    let (added, removed) = match st.merge_live() {
+        Ok(changes) => changes,
+        Err(e) => {
+            ui.set_status_text(slint::SharedString::from(format!("Refresh failed: {e}")));
+            return;
+        }
+    };
+    let index = selected.and_then(|name| st.monitors.iter().position(|m| m.name == name));
+    ui.set_selected_index(index.map_or(-1, |i| i as i32));
+    push_state_to_ui(ui, st);
+
+    let mut events: Vec<String> = added.iter().map(|n| format!("{n} connected")).collect();
+    events.extend(removed.iter().map(|n| format!("{n} disconnected")));
+    if !events.is_empty() {
+        ui.set_status_text(slint::SharedString::from(events.join(" | ")));
+    }
+}
+
+/// End an apply transaction. `keep` saves the applied layout (config file
+/// and profile); otherwise the previous layout is applied again.
+fn finish_apply(ui: &App, st: &mut AppState, keep: bool) {
+    ui.set_confirm_visible(false);
+    let status = if keep { st.keep_applied() } else { st.roll_back() };
+    ui.set_status_text(slint::SharedString::from(status));
+    push_state_to_ui(ui, st);
+}
+
+/// `--demo`, `--demo=SCENARIO.json` and `--demo-log=FILE`.
+fn is_demo_arg(arg: &str) -> bool {
+    arg == "--demo" || arg.starts_with("--demo=") || arg.starts_with("--demo-log=")
+}
+
+/// The demo backend described by the `--demo*` arguments.
+fn demo_backend() -> Result<Box<dyn DisplayBackend>, String> {
+    let mut demo = match std::env::args().find_map(|a| a.strip_prefix("--demo=").map(str::to_string)) {
+        Some(path) => demo::DemoBackend::from_file(std::path::Path::new(&path))?,
+        None => demo::DemoBackend::new(),
+    };
+    if let Some(log) = std::env::args().find_map(|a| a.strip_prefix("--demo-log=").map(str::to_string)) {
+        demo = demo.log_to(log.into())?;
+    }
+    Ok(Box::new(demo))
 }
 
 fn main() -> Result<(), slint::PlatformError> {
-    let use_demo = std::env::args().any(|a| a == "--demo");
+    let use_demo = std::env::args().any(|a| a == "--demo" || a.starts_with("--demo="));
 
     for arg in std::env::args() {
         if arg == "-v" || arg == "--version" {
             println!("disp-center v{}", env!("CARGO_PKG_VERSION"));
             return Ok(());
         }
+        if arg == "-h" || arg == "--help" {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
     }
 
-    // Set up winit backend with app_id for Hyprland matching, no CSD, femtovg renderer
+    // Headless commands: list, set, apply, save
+    let args: Vec<String> = std::env::args().skip(1).filter(|a| !is_demo_arg(a)).collect();
+    if args.first().is_some_and(|a| a == "help") {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if args.first().is_some_and(|a| cli::is_command(a)) {
+        let display_backend = if use_demo { demo_backend() } else { backend::detect_backend() };
+        if let Err(e) = display_backend.and_then(|b| cli::run(b.as_ref(), &args)) {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Headless hotplug daemon: apply saved profiles when monitors change
+    if std::env::args().any(|a| a == "--daemon") {
+        let result = backend::detect_backend().and_then(|b| daemon::run(b.as_ref()));
+        if let Err(e) = result {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Set up winit backend with app_id for Hyprland matching, no CSD, femtovg renderer.
+    // The main window is created first; the identify overlays after it get
+    // their own app_id and keep the size of their component.
+    let main_created = std::cell::Cell::new(false);
     let backend = i_slint_backend_winit::Backend::builder()
         .with_renderer_name("renderer-software")
-        .with_window_attributes_hook(|attrs| {
+        .with_window_attributes_hook(move |attrs| {
             use i_slint_backend_winit::winit::platform::wayland::WindowAttributesExtWayland;
             use i_slint_backend_winit::winit::dpi::LogicalSize;
+            let attrs = attrs.with_decorations(false);
+            if main_created.replace(true) {
+                return attrs.with_name("disp-center-identify", "disp-center-identify");
+            }
             attrs
                 .with_name("disp-center", "disp-center")
-                .with_decorations(false)
                 .with_inner_size(LogicalSize::new(680.0_f64, 520.0))
         })
         .build()?;
@@ -217,7 +852,13 @@ fn main() -> Result<(), slint::PlatformError> {
 
     let display_backend: Box<dyn DisplayBackend> = if use_demo {
         eprintln!("Running in demo mode with mock monitors");
-        Box::new(demo::DemoBackend::new())
+        match demo_backend() {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
     } else {
         match backend::detect_backend() {
             Ok(b) => b,
@@ -231,22 +872,40 @@ fn main() -> Result<(), slint::PlatformError> {
 
     let state = Rc::new(RefCell::new(AppState::new(display_backend)));
 
-    if let Err(e) = state.borrow_mut().load_monitors() {
-        eprintln!("Failed to query monitors: {e}");
-        std::process::exit(1);
-    }
+    let converted = match state.borrow_mut().load_monitors() {
+        Ok(converted) => converted,
+        Err(e) => {
+            eprintln!("Failed to query monitors: {e}");
+            std::process::exit(1);
+        }
+    };
 
     let ui = App::new()?;
     apply_theme(&ui);
     push_state_to_ui(&ui, &state.borrow());
+    ui.set_supports_advanced(state.borrow().backend.supports_advanced());
+    ui.set_supports_workspaces(state.borrow().backend.supports_workspaces());
     ui.set_status_text(slint::SharedString::from(format!(
-        "Backend: {} | {} monitor(s) detected",
+        "Backend: {} | {} monitor(s) detected{}",
         state.borrow().backend.name(),
-        state.borrow().monitors.len()
+        state.borrow().monitors.len(),
+        if converted { " | Positions converted to logical pixels, apply to fix gaps" } else { "" },
     )));
 
     // -- Close --
-    ui.on_close(|| { std::process::exit(0); });
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_close(move || {
+            // Closing with an unconfirmed apply rolls it back.
+            if let Some(ui) = ui_handle.upgrade() {
+                if ui.get_confirm_visible() {
+                    finish_apply(&ui, &mut state.borrow_mut(), false);
+                }
+            }
+            std::process::exit(0);
+        });
+    }
 
     // -- Window drag (manual position tracking, works reliably on X11) --
     {
@@ -275,24 +934,16 @@ fn main() -> Result<(), slint::PlatformError> {
             let ui = ui_handle.unwrap();
             let st = state.borrow();
             if let Some(m) = st.monitors.get(idx as usize) {
-                let modes: Vec<slint::SharedString> = m
-                    .available_modes
-                    .iter()
-                    .map(|mode| slint::SharedString::from(mode.label()))
-                    .collect();
+                let (modes, mode_idx) = mode_options(m);
                 ui.set_selected_modes(slint::ModelRc::new(slint::VecModel::from(modes)));
+                ui.set_selected_mode_index(mode_idx);
+                push_scale_to_ui(&ui, m);
+                ui.set_selected_transform(m.transform);
 
-                let mode_idx = m
-                    .available_modes
-                    .iter()
-                    .position(|mode| {
-                        mode.width == m.width
-                            && mode.height == m.height
-                            && (mode.refresh_rate - m.refresh_rate).abs() < 1.0
-                    })
-                    .unwrap_or(0);
-                ui.set_selected_mode_index(mode_idx as i32);
-                ui.set_selected_scale(m.scale as f32);
+                let (options, current) = st.mirror_options(idx as usize);
+                ui.set_mirror_options(slint::ModelRc::new(slint::VecModel::from(options)));
+                ui.set_selected_mirror_index(current);
+                push_advanced_to_ui(&ui, m);
             }
         });
     }
@@ -306,24 +957,36 @@ fn main() -> Result<(), slint::PlatformError> {
             let idx = idx as usize;
             if idx >= st.monitors.len() { return; }
 
-            let real_x = ((canvas_x as f64 - 20.0) / st.scale - st.offset_x) as i32;
-            let real_y = ((canvas_y as f64 - 20.0) / st.scale - st.offset_y) as i32;
-            st.monitors[idx].x = real_x;
-            st.monitors[idx].y = real_y;
+            // Mirrored monitors follow their target; snap back.
+            if st.monitors[idx].mirror.is_some() {
+                push_state_to_ui(&ui_handle.unwrap(), &st);
+                return;
+            }
+
+            st.edit(None, |st| {
+                let real_x = ((canvas_x as f64 - 20.0) / st.scale - st.offset_x) as i32;
+                let real_y = ((canvas_y as f64 - 20.0) / st.scale - st.offset_y) as i32;
+                st.monitors[idx].x = real_x;
+                st.monitors[idx].y = real_y;
 
-            let others: Vec<(i32, i32, i32, i32)> = st
-                .monitors.iter().enumerate()
-                .filter(|(i, _)| *i != idx)
-                .map(|(_, m)| (m.x, m.y, m.width, m.height))
-                .collect();
+                let others: Vec<(i32, i32, i32, i32)> = st
+                    .monitors.iter().enumerate()
+                    .filter(|(i, m)| *i != idx && m.mirror.is_none())
+                    .map(|(_, m)| {
+                        let (w, h) = m.logical_size();
+                        (m.x, m.y, w, h)
+                    })
+                    .collect();
 
-            let m = &st.monitors[idx];
-            let snap_threshold = (50.0 / st.scale) as i32;
-            let (sx, sy) = snap_to_nearest_edge(m.x, m.y, m.width, m.height, &others, snap_threshold);
-            st.monitors[idx].x = sx;
-            st.monitors[idx].y = sy;
+                let m = &st.monitors[idx];
+                let (w, h) = m.logical_size();
+                let snap_threshold = (50.0 / st.scale) as i32;
+                let (sx, sy) = snap_to_nearest_edge(m.x, m.y, w, h, &others, snap_threshold);
+                st.monitors[idx].x = sx;
+                st.monitors[idx].y = sy;
 
-            st.recalc_canvas();
+                st.recalc_canvas();
+            });
             let ui = ui_handle.unwrap();
             push_state_to_ui(&ui, &st);
         });
@@ -339,32 +1002,178 @@ fn main() -> Result<(), slint::PlatformError> {
             let modi = mode_idx as usize;
             if mi < st.monitors.len() && modi < st.monitors[mi].available_modes.len() {
                 let mode = st.monitors[mi].available_modes[modi].clone();
-                st.monitors[mi].width = mode.width;
-                st.monitors[mi].height = mode.height;
-                st.monitors[mi].refresh_rate = mode.refresh_rate;
-                st.recalc_canvas();
+                st.edit(None, |st| {
+                    st.resize(mi, |m| {
+                        m.width = mode.width;
+                        m.height = mode.height;
+                        m.refresh_rate = mode.refresh_rate;
+                        m.custom_mode = None;
+                        // Keep the scale usable for the new resolution.
+                        m.scale = layout::nearest_valid_scale(m.scale, m.width, m.height);
+                    })
+                });
             }
             let ui = ui_handle.unwrap();
             push_state_to_ui(&ui, &st);
         });
     }
 
+    // -- Custom mode dialog --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_open_custom_mode(move |mon_idx| {
+            let st = state.borrow();
+            let ui = ui_handle.unwrap();
+            let Some(m) = st.monitors.get(mon_idx as usize) else { return };
+            ui.set_custom_width(slint::SharedString::from(m.width.to_string()));
+            ui.set_custom_height(slint::SharedString::from(m.height.to_string()));
+            ui.set_custom_refresh(slint::SharedString::from(format!("{:.0}", m.refresh_rate)));
+            ui.set_custom_reduced(m.custom_mode.as_ref().is_some_and(|c| c.reduced));
+            // The dropdown shows "Custom mode…" now; the mode stays as is
+            // until the dialog applies.
+            ui.set_selected_mode_index(mode_options(m).1);
+            ui.set_custom_mode_visible(true);
+        });
+    }
+    ui.on_custom_modeline(|width, height, refresh, reduced| {
+        let text = match parse_custom_mode(&width, &height, &refresh, reduced) {
+            Ok(modeline) => format!("{modeline}\n{:.3} Hz", modeline.refresh_rate()),
+            Err(e) => e,
+        };
+        slint::SharedString::from(text)
+    });
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_apply_custom_mode(move |mon_idx, width, height, refresh, reduced| {
+            let ui = ui_handle.unwrap();
+            let modeline = match parse_custom_mode(&width, &height, &refresh, reduced) {
+                Ok(modeline) => modeline,
+                Err(e) => {
+                    ui.set_status_text(slint::SharedString::from(format!("Custom mode: {e}")));
+                    return;
+                }
+            };
+            {
+                let mut st = state.borrow_mut();
+                if mon_idx < 0 || mon_idx as usize >= st.monitors.len() {
+                    return;
+                }
+                st.edit(None, |st| {
+                    st.resize(mon_idx as usize, |m| {
+                        let mode = modeline.mode();
+                        m.width = mode.width;
+                        m.height = mode.height;
+                        m.refresh_rate = mode.refresh_rate;
+                        m.scale = layout::nearest_valid_scale(m.scale, m.width, m.height);
+                        m.custom_mode = Some(modeline);
+                    })
+                });
+                push_state_to_ui(&ui, &st);
+            }
+            ui.set_custom_mode_visible(false);
+            // Through the usual apply, so a mode the monitor can't show
+            // rolls back by itself.
+            ui.invoke_apply_changes();
+        });
+    }
+
     // -- Change scale --
     {
         let state = state.clone();
         let ui_handle = ui.as_weak();
-        ui.on_change_scale(move |mon_idx, scale| {
+        ui.on_change_scale(move |mon_idx, scale_idx| {
+            let mut st = state.borrow_mut();
+            st.edit(Some(&format!("scale {mon_idx}")), |st| {
+                st.resize(mon_idx as usize, |m| {
+                    if let Some(scale) = layout::valid_scales(m.width, m.height).get(scale_idx as usize) {
+                        m.scale = *scale;
+                    }
+                })
+            });
+            let ui = ui_handle.unwrap();
+            push_state_to_ui(&ui, &st);
+        });
+    }
+
+    // -- Change rotation --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_change_transform(move |mon_idx, transform| {
+            let mut st = state.borrow_mut();
+            st.edit(None, |st| st.resize(mon_idx as usize, |m| m.transform = transform));
+            let ui = ui_handle.unwrap();
+            push_state_to_ui(&ui, &st);
+        });
+    }
+
+    // -- Change mirror target (option 0 = not mirrored) --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_change_mirror(move |mon_idx, option| {
             let mut st = state.borrow_mut();
             let mi = mon_idx as usize;
             if mi < st.monitors.len() {
-                st.monitors[mi].scale = scale as f64;
-                st.recalc_canvas();
+                let target = if option > 0 {
+                    st.mirror_targets(mi).get(option as usize - 1).cloned()
+                } else {
+                    None
+                };
+                st.edit(None, |st| {
+                    if target.is_some() {
+                        // No chains: whatever mirrored this monitor goes back to extending.
+                        let name = st.monitors[mi].name.clone();
+                        for m in st.monitors.iter_mut() {
+                            if m.mirror.as_ref() == Some(&name) {
+                                m.mirror = None;
+                            }
+                        }
+                    }
+                    st.monitors[mi].mirror = target;
+                    st.recalc_canvas();
+                });
             }
             let ui = ui_handle.unwrap();
             push_state_to_ui(&ui, &st);
         });
     }
 
+    // -- Advanced options (VRR, bit depth, color management) --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_change_advanced(move |mon_idx, vrr, ten_bit, color_mode, sdr_brightness| {
+            let mut st = state.borrow_mut();
+            st.edit(Some(&format!("advanced {mon_idx}")), |st| {
+                if let Some(m) = st.monitors.get_mut(mon_idx as usize) {
+                    m.options.vrr = vrr;
+                    m.options.ten_bit = ten_bit;
+                    m.options.color_mode = COLOR_MODES
+                        .get(color_mode as usize)
+                        .unwrap_or(&"auto")
+                        .to_string();
+                    m.options.sdr_brightness = sdr_brightness as f64;
+                }
+            });
+            let ui = ui_handle.unwrap();
+            push_state_to_ui(&ui, &st);
+        });
+    }
+
+    // -- Workspace assignment: click cycles through the monitors --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_cycle_workspace(move |idx| {
+            let mut st = state.borrow_mut();
+            st.edit(None, |st| st.cycle_workspace(idx as usize));
+            push_state_to_ui(&ui_handle.unwrap(), &st);
+        });
+    }
+
     // -- Set primary --
     {
         let state = state.clone();
@@ -373,64 +1182,145 @@ fn main() -> Result<(), slint::PlatformError> {
             let mut st = state.borrow_mut();
             let mi = mon_idx as usize;
             if mi < st.monitors.len() {
-                st.primary = st.monitors[mi].name.clone();
-                let name = st.primary.clone();
-                let _ = st.backend.set_primary(&name);
+                let name = st.monitors[mi].name.clone();
+                st.edit(None, |st| {
+                    st.primary = name.clone();
+                    // Kept in the workspace rules so it survives a restart.
+                    st.workspaces[0] = name;
+                });
             }
             let ui = ui_handle.unwrap();
             push_state_to_ui(&ui, &st);
-            ui.set_status_text(slint::SharedString::from("Primary monitor updated"));
+            ui.set_status_text(slint::SharedString::from("Primary monitor changed (apply to use it)"));
         });
     }
 
-    // -- Apply --
+    // -- Lid policy: takes effect right away, not on apply --
     {
         let state = state.clone();
         let ui_handle = ui.as_weak();
-        ui.on_apply_changes(move || {
+        ui.on_change_lid_policy(move |idx| {
             let mut st = state.borrow_mut();
-            let configs = st.configs_from_current();
             let ui = ui_handle.unwrap();
-
-            match st.backend.apply(&configs) {
+            let Some(policy) = st.lid_policies().get(idx as usize).copied() else { return };
+            let Some(internal) = st.internal_config() else {
+                ui.set_status_text(slint::SharedString::from("No built-in display"));
+                return;
+            };
+            match st.backend.set_lid_policy(policy, &internal) {
                 Ok(()) => {
-                    match st.backend.persist(&configs) {
-                        Ok(path) => {
-                            ui.set_status_text(slint::SharedString::from(format!(
-                                "Applied and saved to {path}"
-                            )));
-                        }
-                        Err(e) => {
-                            ui.set_status_text(slint::SharedString::from(format!(
-                                "Applied live but failed to save: {e}"
-                            )));
-                        }
-                    }
-                    st.original = st.monitors.clone();
+                    st.lid_policy = policy;
+                    ui.set_status_text(slint::SharedString::from(format!(
+                        "When lid is closed: {}",
+                        policy.label()
+                    )));
                 }
                 Err(e) => {
-                    ui.set_status_text(slint::SharedString::from(format!("Apply failed: {e}")));
+                    ui.set_status_text(slint::SharedString::from(format!("Lid setting failed: {e}")))
                 }
             }
             push_state_to_ui(&ui, &st);
         });
     }
 
+    // -- Apply: live, then wait for confirmation before saving --
+    let confirm_timer = Rc::new(slint::Timer::default());
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        let confirm_timer = confirm_timer.clone();
+        ui.on_apply_changes(move || {
+            let ui = ui_handle.unwrap();
+            match state.borrow().apply_live() {
+                Ok(None) => {}
+                Ok(Some(warning)) => ui.set_status_text(slint::SharedString::from(warning)),
+                Err(status) => {
+                    ui.set_status_text(slint::SharedString::from(status));
+                    return;
+                }
+            }
+
+            ui.set_confirm_seconds(CONFIRM_SECS);
+            ui.set_confirm_visible(true);
+
+            // Runs on the event loop, so it fires even when the output this
+            // window is on went dark.
+            let state = state.clone();
+            let ui_handle = ui_handle.clone();
+            let timer = confirm_timer.clone();
+            confirm_timer.start(
+                slint::TimerMode::Repeated,
+                std::time::Duration::from_secs(1),
+                move || {
+                    let Some(ui) = ui_handle.upgrade() else { return };
+                    let left = ui.get_confirm_seconds() - 1;
+                    ui.set_confirm_seconds(left);
+                    if left <= 0 {
+                        timer.stop();
+                        finish_apply(&ui, &mut state.borrow_mut(), false);
+                    }
+                },
+            );
+        });
+    }
+
+    // -- Confirm dialog: keep or roll back --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        let confirm_timer = confirm_timer.clone();
+        ui.on_confirm_apply(move |keep| {
+            confirm_timer.stop();
+            let ui = ui_handle.unwrap();
+            finish_apply(&ui, &mut state.borrow_mut(), keep);
+        });
+    }
+
     // -- Revert --
     {
         let state = state.clone();
         let ui_handle = ui.as_weak();
         ui.on_revert_changes(move || {
             let mut st = state.borrow_mut();
-            st.monitors = st.original.clone();
-            st.recalc_canvas();
+            let original = st.original_snapshot();
+            st.reset_to("revert", original);
             let ui = ui_handle.unwrap();
             push_state_to_ui(&ui, &st);
-            ui.set_status_text(slint::SharedString::from("Reverted to original layout"));
+            ui.set_status_text(slint::SharedString::from("Reverted to original layout (Ctrl+Z to undo)"));
             ui.set_selected_index(-1);
         });
     }
 
+    // -- Undo / redo --
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_undo(move || {
+            let mut st = state.borrow_mut();
+            let ui = ui_handle.unwrap();
+            let status = match st.undo() {
+                Some(step) => format!("Undid: {step}"),
+                None => "Nothing to undo".into(),
+            };
+            push_state_to_ui(&ui, &st);
+            ui.set_status_text(slint::SharedString::from(status));
+        });
+    }
+    {
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        ui.on_redo(move || {
+            let mut st = state.borrow_mut();
+            let ui = ui_handle.unwrap();
+            let status = match st.redo() {
+                Some(step) => format!("Redid: {step}"),
+                None => "Nothing to redo".into(),
+            };
+            push_state_to_ui(&ui, &st);
+            ui.set_status_text(slint::SharedString::from(status));
+        });
+    }
+
     // -- Refresh --
     {
         let state = state.clone();
@@ -439,10 +1329,12 @@ fn main() -> Result<(), slint::PlatformError> {
             let mut st = state.borrow_mut();
             let ui = ui_handle.unwrap();
             match st.load_monitors() {
-                Ok(()) => {
+                Ok(converted) => {
                     push_state_to_ui(&ui, &st);
                     ui.set_status_text(slint::SharedString::from(format!(
-                        "{} monitor(s) detected", st.monitors.len()
+                        "{} monitor(s) detected{}",
+                        st.monitors.len(),
+                        if converted { " | Positions converted to logical pixels" } else { "" },
                     )));
                     ui.set_selected_index(-1);
                 }
@@ -453,21 +1345,80 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
+    // -- Live refresh on hotplug --
+    {
+        let ui_weak = ui.as_weak();
+        let changed = Box::new(move || {
+            let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_monitors_changed());
+        });
+        if let Err(e) = state.borrow().backend.watch(changed) {
+            eprintln!("Live refresh unavailable: {e}");
+        }
+
+        let state = state.clone();
+        let ui_handle = ui.as_weak();
+        let settle_timer = slint::Timer::default();
+        ui.on_monitors_changed(move || {
+            let state = state.clone();
+            let ui_handle = ui_handle.clone();
+            settle_timer.start(slint::TimerMode::SingleShot, HOTPLUG_SETTLE, move || {
+                let Some(ui) = ui_handle.upgrade() else { return };
+                // The rollback needs the layout from before the apply; wait
+                // until the user has kept or reverted it.
+                if ui.get_confirm_visible() {
+                    ui.invoke_monitors_changed();
+                    return;
+                }
+                refresh_live(&ui, &mut state.borrow_mut());
+            });
+        });
+    }
+
     // -- Identify --
     {
         let state = state.clone();
         let ui_handle = ui.as_weak();
+        let overlays: Rc<RefCell<Vec<Overlay>>> = Rc::new(RefCell::new(Vec::new()));
+        let place_timer = slint::Timer::default();
+        let close_timer = slint::Timer::default();
         ui.on_identify_monitors(move || {
-            let st = state.borrow();
             let ui = ui_handle.unwrap();
-            match st.backend.identify(&st.monitors) {
-                Ok(()) => {
-                    ui.set_status_text(slint::SharedString::from("Identifying monitors..."));
-                }
+            for overlay in overlays.borrow_mut().drain(..) {
+                let _ = overlay.window.hide();
+            }
+            match open_identify_overlays(&state.borrow().monitors) {
+                Ok(opened) => *overlays.borrow_mut() = opened,
                 Err(e) => {
                     ui.set_status_text(slint::SharedString::from(format!("Identify failed: {e}")));
+                    return;
                 }
             }
+            ui.set_status_text(slint::SharedString::from("Identifying monitors..."));
+
+            // The compositor can only move the windows once they are mapped.
+            let (state, placed) = (state.clone(), overlays.clone());
+            place_timer.start(
+                slint::TimerMode::SingleShot,
+                std::time::Duration::from_millis(150),
+                move || {
+                    let st = state.borrow();
+                    for o in placed.borrow().iter() {
+                        if let Err(e) = st.backend.place_overlay(&o.title, o.x, o.y) {
+                            eprintln!("Failed to place '{}': {e}", o.title);
+                        }
+                    }
+                },
+            );
+            let overlays = overlays.clone();
+            close_timer.start(
+                slint::TimerMode::SingleShot,
+                std::time::Duration::from_secs(IDENTIFY_SECS),
+                move || {
+                    for overlay in overlays.borrow_mut().drain(..) {
+                        let _ = overlay.window.hide();
+                    }
+                },
+            );
         });
     }
 
@@ -489,3 +1440,186 @@ fn main() -> Result<(), slint::PlatformError> {
 
     ui.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory for one test.
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("disp-center-test-{}", std::process::id())).join(test);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// App state over the demo backend, with `scenario` (JSON, see
+    /// `demo::Scenario`) if not empty, and the path of its call log.
+    /// Profiles go to the test's directory instead of the user's.
+    fn demo_state(test: &str, scenario: &str) -> (AppState, PathBuf) {
+        let dir = scratch_dir(test);
+        let backend = if scenario.is_empty() {
+            demo::DemoBackend::new()
+        } else {
+            let path = dir.join("scenario.json");
+            std::fs::write(&path, scenario).unwrap();
+            demo::DemoBackend::from_file(&path).unwrap()
+        };
+        let log = dir.join("calls.jsonl");
+        let mut st = AppState::new(Box::new(backend.log_to(log.clone()).unwrap()));
+        st.profiles = dir.join("profiles.json");
+        st.load_monitors().unwrap();
+        (st, log)
+    }
+
+    fn calls(log: &PathBuf) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn call_names(log: &PathBuf) -> Vec<String> {
+        calls(log).iter().map(|c| c["call"].as_str().unwrap().to_string()).collect()
+    }
+
+    /// Refresh rate HDMI-A-1 was set to by the `n`th logged call.
+    fn applied_rate(log: &PathBuf, n: usize) -> f64 {
+        let call = &calls(log)[n];
+        let configs = call["configs"].as_array().unwrap();
+        let hdmi = configs.iter().find(|c| c["name"] == "HDMI-A-1").unwrap();
+        hdmi["refresh_rate"].as_f64().unwrap()
+    }
+
+    fn set_hdmi_rate(st: &mut AppState, rate: f64) {
+        st.edit(None, |st| st.monitors[1].refresh_rate = rate);
+    }
+
+    #[test]
+    fn apply_then_keep_persists() {
+        let (mut st, log) = demo_state("keep", "");
+        set_hdmi_rate(&mut st, 144.0);
+        assert!(st.has_changes());
+
+        assert_eq!(st.apply_live(), Ok(None));
+        assert_eq!(st.keep_applied(), "Applied and saved to (demo mode - not saved)");
+        assert!(!st.has_changes());
+
+        assert_eq!(call_names(&log), ["apply", "apply_workspaces", "persist"]);
+        assert_eq!(applied_rate(&log, 0), 144.0);
+        assert_eq!(applied_rate(&log, 2), 144.0);
+        assert_eq!(profile::load(&st.profiles).len(), 1);
+    }
+
+    #[test]
+    fn timeout_rolls_back() {
+        let (mut st, log) = demo_state("timeout", "");
+        set_hdmi_rate(&mut st, 144.0);
+        assert_eq!(st.apply_live(), Ok(None));
+
+        // What the confirm countdown does when it runs out.
+        assert_eq!(st.roll_back(), "Reverted to previous settings");
+        assert_eq!(st.monitors[1].refresh_rate, 60.0);
+        assert!(!st.has_changes());
+        assert_eq!(call_names(&log), ["apply", "apply_workspaces", "apply", "apply_workspaces"]);
+        assert_eq!(applied_rate(&log, 2), 60.0);
+        assert!(!call_names(&log).contains(&"persist".to_string()));
+
+        // The rejected layout can be brought back.
+        assert_eq!(st.undo().as_deref(), Some("roll back"));
+        assert_eq!(st.monitors[1].refresh_rate, 144.0);
+    }
+
+    #[test]
+    fn cleared_workspaces_are_persisted_as_unassigned() {
+        let (mut st, log) = demo_state("unassign", "");
+        st.edit(None, |st| st.workspaces[2].clear());
+        assert_eq!(st.apply_live(), Ok(None));
+        st.keep_applied();
+
+        let persist = calls(&log).pop().unwrap();
+        assert_eq!(
+            persist["workspaces"],
+            serde_json::json!([
+                {"workspace": 1, "monitor": "DP-1"},
+                {"workspace": 2, "monitor": "DP-1"},
+                {"workspace": 3, "monitor": ""},
+            ])
+        );
+    }
+
+    #[test]
+    fn persist_failure_keeps_live_layout() {
+        let (mut st, log) = demo_state("persist-fail", r#"{"fail": {"persist": "disk full"}}"#);
+        set_hdmi_rate(&mut st, 144.0);
+        assert_eq!(st.apply_live(), Ok(None));
+        assert_eq!(st.keep_applied(), "Applied live but failed to save: disk full");
+
+        let persist = calls(&log).pop().unwrap();
+        assert_eq!(persist["call"], "persist");
+        assert_eq!(persist["error"], "disk full");
+        // The layout is live, so it's the new baseline either way.
+        assert!(!st.has_changes());
+    }
+
+    #[test]
+    fn apply_failure_is_reported() {
+        let (mut st, log) = demo_state("apply-fail", r#"{"fail": {"apply": "output busy"}}"#);
+        set_hdmi_rate(&mut st, 144.0);
+        assert_eq!(st.apply_live(), Err("Apply failed: output busy".to_string()));
+        assert_eq!(call_names(&log), ["apply"]);
+        assert!(st.has_changes());
+    }
+
+    #[test]
+    fn workspace_failure_is_a_warning() {
+        let (mut st, log) = demo_state("workspaces-fail", r#"{"fail": {"apply_workspaces": "no such workspace"}}"#);
+        set_hdmi_rate(&mut st, 144.0);
+        assert_eq!(st.apply_live(), Ok(Some("Moving workspaces failed: no such workspace".to_string())));
+        assert_eq!(call_names(&log), ["apply", "apply_workspaces"]);
+    }
+
+    fn set_hdmi_primary(st: &mut AppState) {
+        st.edit(None, |st| {
+            st.primary = "HDMI-A-1".into();
+            st.workspaces[0] = "HDMI-A-1".into();
+        });
+    }
+
+    #[test]
+    fn primary_waits_for_apply_and_undoes() {
+        let (mut st, log) = demo_state("primary", "");
+        set_hdmi_primary(&mut st);
+        assert!(st.has_changes());
+        assert!(call_names(&log).is_empty());
+
+        assert_eq!(st.undo().as_deref(), Some("primary \u{2192} HDMI-A-1 (+1 more)"));
+        assert_eq!(st.primary, "DP-1");
+        assert!(!st.has_changes());
+
+        st.redo();
+        assert_eq!(st.apply_live(), Ok(None));
+        assert_eq!(call_names(&log), ["apply", "apply_workspaces", "set_primary"]);
+        assert_eq!(calls(&log)[2]["monitor"], "HDMI-A-1");
+    }
+
+    #[test]
+    fn roll_back_restores_primary() {
+        let (mut st, log) = demo_state("primary-roll-back", "");
+        set_hdmi_primary(&mut st);
+        assert_eq!(st.apply_live(), Ok(None));
+        assert_eq!(st.roll_back(), "Reverted to previous settings");
+        assert_eq!(st.primary, "DP-1");
+        assert_eq!(calls(&log).last().unwrap()["monitor"], "DP-1");
+    }
+
+    #[test]
+    fn primary_failure_is_a_warning() {
+        let (mut st, _) = demo_state("primary-fail", r#"{"fail": {"set_primary": "no such output"}}"#);
+        set_hdmi_primary(&mut st);
+        assert_eq!(
+            st.apply_live(),
+            Ok(Some("Setting the primary monitor failed: no such output".to_string()))
+        );
+    }
+}
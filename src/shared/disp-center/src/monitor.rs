@@ -1,11 +1,15 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::cvt::Modeline;
+use crate::edid::{self, Edid};
+use crate::layout::format_scale;
 
 /// A single available display mode (resolution + refresh rate).
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorMode {
     pub width: i32,
     pub height: i32,
-    #[serde(rename = "refreshRate")]
+    #[serde(alias = "refreshRate")]
     pub refresh_rate: f64,
 }
 
@@ -15,8 +19,59 @@ impl MonitorMode {
     }
 }
 
+/// Hyprland `cm` presets, in the order the panel lists them.
+pub const COLOR_MODES: [&str; 6] = ["auto", "srgb", "wide", "edid", "hdr", "hdredid"];
+
+/// Advanced per-output settings (Hyprland only, see `DisplayBackend::supports_advanced`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputOptions {
+    pub vrr: i32, // 0=off, 1=on, 2=fullscreen only
+    pub ten_bit: bool,
+    pub color_mode: String, // one of COLOR_MODES
+    pub sdr_brightness: f64,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self { vrr: 0, ten_bit: false, color_mode: "auto".into(), sdr_brightness: 1.0 }
+    }
+}
+
+impl OutputOptions {
+    /// Extra `, key, value` parameters for a Hyprland monitor line.
+    /// Only settings that differ from the defaults are written.
+    pub fn hyprland_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if self.vrr != 0 {
+            params.push(("vrr", self.vrr.to_string()));
+        }
+        if self.ten_bit {
+            params.push(("bitdepth", "10".into()));
+        }
+        if self.color_mode != "auto" {
+            params.push(("cm", self.color_mode.clone()));
+        }
+        if (self.sdr_brightness - 1.0).abs() > 0.001 {
+            params.push(("sdrbrightness", format!("{:.2}", self.sdr_brightness)));
+        }
+        params
+    }
+}
+
+/// Workspaces 1..=WORKSPACES can be assigned to a monitor.
+pub const WORKSPACES: i32 = 10;
+
+/// Workspace `workspace` opens on monitor `monitor` (a connector name).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceRule {
+    pub workspace: i32,
+    pub monitor: String,
+}
+
 /// Represents one physical display as reported by the compositor.
-#[derive(Debug, Clone)]
+/// Missing fields default when read from a demo scenario (see demo.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(dead_code)]
 pub struct Monitor {
     pub id: i32,
@@ -25,18 +80,26 @@ pub struct Monitor {
     pub width: i32,          // current active resolution
     pub height: i32,
     pub refresh_rate: f64,
-    pub x: i32, // position in the global compositor space
+    pub x: i32, // position in the global compositor space (logical pixels)
     pub y: i32,
     pub scale: f64,
-    pub transform: i32,  // 0=normal, 1=90, 2=180, 3=270
+    pub transform: i32,  // 0=normal, 1=90, 2=180, 3=270, 4-7 = flipped + same
     pub enabled: bool,
     pub dpms: bool,
     pub focused: bool,
+    pub mirror: Option<String>, // name of the monitor this one mirrors
+    pub options: OutputOptions,
     pub available_modes: Vec<MonitorMode>,
+    /// Generated timings of the current mode when it isn't one of
+    /// `available_modes` (set from the "Custom mode" dialog).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_mode: Option<Modeline>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edid: Option<Edid>, // None when the EDID can't be read
 }
 
 /// Configuration to apply to a single monitor.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MonitorConfig {
     pub name: String,
     pub width: i32,
@@ -45,25 +108,118 @@ pub struct MonitorConfig {
     pub x: i32,
     pub y: i32,
     pub scale: f64,
+    pub transform: i32, // same values as Monitor::transform
+    pub mirror: Option<String>,
+    pub options: OutputOptions,
     pub enabled: bool,
+    /// Custom timings; the size and rate above match them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modeline: Option<Modeline>,
 }
 
 impl MonitorConfig {
-    /// Format as Hyprland monitor line: `monitor = NAME, WxH@HZ, XxY, SCALE`
+    /// The monitor's current settings.
+    pub fn from_monitor(m: &Monitor) -> Self {
+        Self {
+            name: m.name.clone(),
+            width: m.width,
+            height: m.height,
+            refresh_rate: m.refresh_rate,
+            x: m.x,
+            y: m.y,
+            scale: m.scale,
+            transform: m.transform,
+            mirror: m.mirror.clone(),
+            options: m.options.clone(),
+            enabled: m.enabled,
+            modeline: m.custom_mode.clone(),
+        }
+    }
+
+    /// Hyprland's mode field: `WxH@HZ`, or `modeline ...` for custom timings.
+    pub fn hyprland_mode(&self) -> String {
+        match &self.modeline {
+            Some(modeline) => format!("modeline {}", modeline.timings()),
+            None => format!("{}x{}@{:.2}", self.width, self.height, self.refresh_rate),
+        }
+    }
+
+    /// Format as Hyprland monitor line:
+    /// `monitor = NAME, WxH@HZ, XxY, SCALE, transform, N[, mirror, TARGET][, vrr, N ...]`
     pub fn to_hyprland_line(&self) -> String {
         if !self.enabled {
             return format!("monitor = {}, disable", self.name);
         }
-        format!(
-            "monitor = {}, {}x{}@{:.2}, {}x{}, {:.2}",
-            self.name, self.width, self.height, self.refresh_rate, self.x, self.y, self.scale,
-        )
+        let mut line = format!(
+            "monitor = {}, {}, {}x{}, {}, transform, {}",
+            self.name, self.hyprland_mode(), self.x, self.y,
+            format_scale(self.scale), self.transform,
+        );
+        if let Some(target) = &self.mirror {
+            line.push_str(&format!(", mirror, {target}"));
+        }
+        for (key, value) in self.options.hyprland_params() {
+            line.push_str(&format!(", {key}, {value}"));
+        }
+        line
+    }
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            description: String::new(),
+            width: 0,
+            height: 0,
+            refresh_rate: 60.0,
+            x: 0,
+            y: 0,
+            scale: 1.0,
+            transform: 0,
+            enabled: true,
+            dpms: true,
+            focused: false,
+            mirror: None,
+            options: OutputOptions::default(),
+            available_modes: Vec::new(),
+            custom_mode: None,
+            edid: None,
+        }
+    }
+}
+
+impl Monitor {
+    /// Size on the desktop: width and height swap for 90/270 rotations.
+    pub fn rotated_size(&self) -> (i32, i32) {
+        rotated_size(self.width, self.height, self.transform)
+    }
+
+    /// Size in the compositor's layout: rotated size divided by scale.
+    pub fn logical_size(&self) -> (i32, i32) {
+        let (w, h) = self.rotated_size();
+        ((w as f64 / self.scale).round() as i32, (h as f64 / self.scale).round() as i32)
+    }
+
+    /// Scale suggested by the panel's DPI at the current mode.
+    pub fn recommended_scale(&self) -> Option<f64> {
+        self.edid.as_ref()?.recommended_scale(self.width, self.height, edid::is_builtin(&self.name))
+    }
+}
+
+/// Swap width and height for transforms 1, 3, 5 and 7 (the 90/270 variants).
+pub fn rotated_size(width: i32, height: i32, transform: i32) -> (i32, i32) {
+    if transform % 2 == 1 {
+        (height, width)
+    } else {
+        (width, height)
     }
 }
 
 /// Edge-snapping: given a monitor being dragged, snap it to the nearest
 /// edge of another monitor. Returns the snapped (x, y) position.
-/// `canvas_scale` converts real pixels → canvas pixels.
+/// Sizes and positions are in logical pixels.
 pub fn snap_to_nearest_edge(
     dragged_x: i32,
     dragged_y: i32,
@@ -113,8 +269,9 @@ pub fn snap_to_nearest_edge(
 }
 
 /// Calculate a uniform scale factor so all monitors fit inside the given canvas dimensions.
+/// Mirrored monitors don't take up space of their own and are skipped.
 pub fn canvas_scale_factor(monitors: &[Monitor], canvas_w: f64, canvas_h: f64) -> f64 {
-    if monitors.is_empty() {
+    if monitors.iter().all(|m| m.mirror.is_some()) {
         return 1.0;
     }
 
@@ -123,11 +280,12 @@ pub fn canvas_scale_factor(monitors: &[Monitor], canvas_w: f64, canvas_h: f64) -
     let mut max_x = i32::MIN;
     let mut max_y = i32::MIN;
 
-    for m in monitors {
+    for m in monitors.iter().filter(|m| m.mirror.is_none()) {
+        let (w, h) = m.logical_size();
         min_x = min_x.min(m.x);
         min_y = min_y.min(m.y);
-        max_x = max_x.max(m.x + m.width);
-        max_y = max_y.max(m.y + m.height);
+        max_x = max_x.max(m.x + w);
+        max_y = max_y.max(m.y + h);
     }
 
     let total_w = (max_x - min_x) as f64;
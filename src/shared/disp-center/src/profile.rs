@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::cvt::Modeline;
+use crate::layout::{physical_to_logical, Placed};
+use crate::monitor::{rotated_size, Monitor, MonitorConfig, OutputOptions, WorkspaceRule};
+
+/// A saved arrangement for one particular set of connected monitors.
+/// Outputs are matched by description (make, model, serial), not by
+/// connector, so a dock can hand out different port names each time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub outputs: Vec<ProfileOutput>,
+    /// Description of the output that gets workspace 1.
+    #[serde(default)]
+    pub primary: String,
+    /// Positions are in logical pixels. Profiles saved before that was
+    /// the case are converted on load.
+    #[serde(default)]
+    pub logical: bool,
+    #[serde(default)]
+    pub workspaces: Vec<ProfileWorkspace>,
+}
+
+/// Workspace assigned to an output, stored by the output's description.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileWorkspace {
+    pub workspace: i32,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileOutput {
+    pub description: String,
+    pub width: i32,
+    pub height: i32,
+    pub refresh_rate: f64,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+    #[serde(default)]
+    pub transform: i32,
+    /// Description of the mirrored output.
+    #[serde(default)]
+    pub mirror: Option<String>,
+    #[serde(default)]
+    pub options: OutputOptions,
+    pub enabled: bool,
+    /// Custom timings when the mode isn't one the monitor lists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modeline: Option<Modeline>,
+}
+
+/// `~/.config/smplos/disp-center/profiles.json`
+pub fn profiles_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.config"))
+        .join("smplos")
+        .join("disp-center")
+        .join("profiles.json")
+}
+
+/// Profiles stored at `path` (normally `profiles_path()`); none if it
+/// doesn't exist or can't be read.
+pub fn load(path: &Path) -> Vec<Profile> {
+    let mut profiles: Vec<Profile> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    for p in profiles.iter_mut().filter(|p| !p.logical) {
+        p.convert_to_logical();
+    }
+    profiles
+}
+
+pub fn save(path: &Path, profiles: &[Profile]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {e}"))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Sorted descriptions of the connected monitors: the key a profile matches on.
+fn monitor_set(descriptions: impl Iterator<Item = String>) -> Vec<String> {
+    let mut set: Vec<String> = descriptions.collect();
+    set.sort();
+    set
+}
+
+impl Profile {
+    /// Capture the current arrangement.
+    pub fn from_monitors(
+        name: &str,
+        monitors: &[Monitor],
+        primary: &str,
+        workspaces: &[WorkspaceRule],
+    ) -> Self {
+        let description = |connector: &str| {
+            monitors.iter().find(|m| m.name == connector).map(|m| m.description.clone())
+        };
+        Self {
+            name: name.to_string(),
+            workspaces: workspaces
+                .iter()
+                .filter_map(|w| {
+                    Some(ProfileWorkspace { workspace: w.workspace, description: description(&w.monitor)? })
+                })
+                .collect(),
+            outputs: monitors
+                .iter()
+                .map(|m| ProfileOutput {
+                    description: m.description.clone(),
+                    width: m.width,
+                    height: m.height,
+                    refresh_rate: m.refresh_rate,
+                    x: m.x,
+                    y: m.y,
+                    scale: m.scale,
+                    transform: m.transform,
+                    mirror: m.mirror.as_deref().and_then(description),
+                    options: m.options.clone(),
+                    enabled: m.enabled,
+                    modeline: m.custom_mode.clone(),
+                })
+                .collect(),
+            primary: description(primary).unwrap_or_default(),
+            logical: true,
+        }
+    }
+
+    /// Move outputs stored in physical pixels to logical positions.
+    fn convert_to_logical(&mut self) {
+        let placed: Vec<Placed> = self
+            .outputs
+            .iter()
+            .map(|o| {
+                let (w, h) = rotated_size(o.width, o.height, o.transform);
+                Placed {
+                    x: o.x,
+                    y: o.y,
+                    physical: (w, h),
+                    logical: ((w as f64 / o.scale).round() as i32, (h as f64 / o.scale).round() as i32),
+                }
+            })
+            .collect();
+        for (o, (x, y)) in self.outputs.iter_mut().zip(physical_to_logical(&placed)) {
+            o.x = x;
+            o.y = y;
+        }
+        self.logical = true;
+    }
+
+    /// Default name for a monitor set, e.g. "LG 27GP950 + BOE 0x0BCA".
+    pub fn default_name(monitors: &[Monitor]) -> String {
+        monitor_set(monitors.iter().map(|m| m.description.clone())).join(" + ")
+    }
+
+    pub fn matches(&self, monitors: &[Monitor]) -> bool {
+        monitor_set(self.outputs.iter().map(|o| o.description.clone()))
+            == monitor_set(monitors.iter().map(|m| m.description.clone()))
+    }
+
+    /// Connected monitor for each profile output (same order as `outputs`).
+    /// Identical monitors are paired up in connector order.
+    fn pair(&self, monitors: &[Monitor]) -> Vec<Option<usize>> {
+        let mut taken = vec![false; monitors.len()];
+        self.outputs
+            .iter()
+            .map(|o| {
+                let idx = (0..monitors.len())
+                    .find(|&i| !taken[i] && monitors[i].description == o.description)?;
+                taken[idx] = true;
+                Some(idx)
+            })
+            .collect()
+    }
+
+    /// Connector of the first connected output with `description`.
+    fn connector(&self, monitors: &[Monitor], description: &str) -> Option<String> {
+        self.outputs
+            .iter()
+            .zip(self.pair(monitors))
+            .find(|(o, idx)| o.description == description && idx.is_some())
+            .and_then(|(_, idx)| Some(monitors[idx?].name.clone()))
+    }
+
+    /// Resolve the profile against the connected monitors: configs keyed by
+    /// connector name, plus the connector that should be primary.
+    pub fn resolve(&self, monitors: &[Monitor]) -> (Vec<MonitorConfig>, Option<String>) {
+        let configs = self
+            .outputs
+            .iter()
+            .zip(self.pair(monitors))
+            .filter_map(|(o, idx)| {
+                let name = monitors[idx?].name.clone();
+                Some(MonitorConfig {
+                    name,
+                    width: o.width,
+                    height: o.height,
+                    refresh_rate: o.refresh_rate,
+                    x: o.x,
+                    y: o.y,
+                    scale: o.scale,
+                    transform: o.transform,
+                    // Stored by description; map it to a connector.
+                    mirror: o.mirror.as_ref().and_then(|desc| self.connector(monitors, desc)),
+                    options: o.options.clone(),
+                    enabled: o.enabled,
+                    modeline: o.modeline.clone(),
+                })
+            })
+            .collect();
+        (configs, self.connector(monitors, &self.primary))
+    }
+
+    /// The profile's workspace assignments for the connected monitors.
+    pub fn resolve_workspaces(&self, monitors: &[Monitor]) -> Vec<WorkspaceRule> {
+        self.workspaces
+            .iter()
+            .filter_map(|w| {
+                Some(WorkspaceRule {
+                    workspace: w.workspace,
+                    monitor: self.connector(monitors, &w.description)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The first profile whose monitor set equals the connected one.
+pub fn find_matching<'a>(profiles: &'a [Profile], monitors: &[Monitor]) -> Option<&'a Profile> {
+    profiles.iter().find(|p| p.matches(monitors))
+}
+
+/// Store `profile`, replacing any profile for the same monitor set.
+pub fn upsert(profiles: &mut Vec<Profile>, profile: Profile) {
+    let key = monitor_set(profile.outputs.iter().map(|o| o.description.clone()));
+    match profiles
+        .iter_mut()
+        .find(|p| monitor_set(p.outputs.iter().map(|o| o.description.clone())) == key)
+    {
+        Some(existing) => {
+            // Keep a name the user gave the profile.
+            let name = std::mem::take(&mut existing.name);
+            *existing = Profile { name, ..profile };
+        }
+        None => profiles.push(profile),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, description: &str, x: i32, scale: f64) -> Monitor {
+        Monitor {
+            name: name.into(),
+            description: description.into(),
+            width: 3840,
+            height: 2160,
+            refresh_rate: 60.0,
+            x,
+            scale,
+            enabled: true,
+            ..Monitor::default()
+        }
+    }
+
+    fn output(description: &str, width: i32, height: i32, x: i32, y: i32, scale: f64) -> ProfileOutput {
+        ProfileOutput {
+            description: description.into(),
+            width,
+            height,
+            refresh_rate: 60.0,
+            x,
+            y,
+            scale,
+            transform: 0,
+            mirror: None,
+            options: OutputOptions::default(),
+            enabled: true,
+            modeline: None,
+        }
+    }
+
+    #[test]
+    fn matches_by_description_regardless_of_connector() {
+        let desk = [monitor("DP-1", "Dell U2720Q", 0, 1.5), monitor("eDP-1", "BOE 0x0BCA", 2560, 1.0)];
+        let profile = Profile::from_monitors("desk", &desk, "DP-1", &[]);
+        assert!(profile.matches(&[monitor("eDP-1", "BOE 0x0BCA", 0, 1.0), monitor("DP-7", "Dell U2720Q", 0, 1.0)]));
+        assert!(!profile.matches(&desk[..1]));
+        let mut more = desk.to_vec();
+        more.push(monitor("HDMI-A-1", "LG 27GP950", 0, 1.0));
+        assert!(!profile.matches(&more));
+    }
+
+    #[test]
+    fn disabled_outputs_stay_in_the_profile() {
+        let mut lid_closed = [monitor("DP-1", "Dell U2720Q", 0, 1.5), monitor("eDP-1", "BOE 0x0BCA", 2560, 1.0)];
+        lid_closed[1].enabled = false;
+        let profile = Profile::from_monitors("docked", &lid_closed, "DP-1", &[]);
+        assert_eq!(profile.outputs.len(), 2);
+        assert!(!profile.outputs[1].enabled);
+        assert!(profile.matches(&lid_closed));
+
+        let (configs, primary) = profile.resolve(&lid_closed);
+        assert!(configs.iter().any(|c| c.name == "eDP-1" && !c.enabled));
+        assert_eq!(primary.as_deref(), Some("DP-1"));
+    }
+
+    #[test]
+    fn identical_monitors_pair_in_connector_order() {
+        let saved = [monitor("DP-1", "Dell U2720Q", 0, 1.5), monitor("DP-2", "Dell U2720Q", 2560, 1.5)];
+        let workspaces = [
+            WorkspaceRule { workspace: 1, monitor: "DP-1".into() },
+            WorkspaceRule { workspace: 2, monitor: "DP-2".into() },
+        ];
+        let profile = Profile::from_monitors("twins", &saved, "DP-2", &workspaces);
+
+        // A dock hands out new connector names.
+        let now = [monitor("DP-5", "Dell U2720Q", 0, 1.0), monitor("DP-6", "Dell U2720Q", 0, 1.0)];
+        let (configs, primary) = profile.resolve(&now);
+        let placed: Vec<(&str, i32)> = configs.iter().map(|c| (c.name.as_str(), c.x)).collect();
+        assert_eq!(placed, [("DP-5", 0), ("DP-6", 2560)]);
+        // Stored by description, so these land on the first of the pair.
+        assert_eq!(primary.as_deref(), Some("DP-5"));
+        let monitors: Vec<String> = profile.resolve_workspaces(&now).into_iter().map(|w| w.monitor).collect();
+        assert_eq!(monitors, ["DP-5", "DP-5"]);
+    }
+
+    #[test]
+    fn old_profiles_move_to_logical_positions() {
+        let mut profile = Profile {
+            name: "old".into(),
+            outputs: vec![
+                output("Dell U2720Q", 3840, 2160, 0, 0, 2.0),
+                output("BOE 0x0BCA", 1920, 1080, 3840, 0, 1.0),
+                output("LG 27GP950", 2560, 1440, 0, 2160, 1.0),
+            ],
+            primary: String::new(),
+            logical: false,
+            workspaces: Vec::new(),
+        };
+        profile.convert_to_logical();
+        let positions: Vec<(i32, i32)> = profile.outputs.iter().map(|o| (o.x, o.y)).collect();
+        assert_eq!(positions, [(0, 0), (1920, 0), (0, 1080)]);
+        assert!(profile.logical);
+    }
+
+    #[test]
+    fn rotated_outputs_convert_with_their_rotated_size() {
+        let mut portrait = output("Dell U2720Q", 3840, 2160, 0, 0, 2.0);
+        portrait.transform = 1;
+        let mut profile = Profile {
+            name: "old".into(),
+            outputs: vec![portrait, output("BOE 0x0BCA", 1920, 1080, 2160, 0, 1.0)],
+            primary: String::new(),
+            logical: false,
+            workspaces: Vec::new(),
+        };
+        profile.convert_to_logical();
+        assert_eq!((profile.outputs[1].x, profile.outputs[1].y), (1080, 0));
+    }
+}
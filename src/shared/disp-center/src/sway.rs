@@ -0,0 +1,743 @@
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use crate::backend::DisplayBackend;
+use crate::edid;
+use crate::hyprconf;
+use crate::layout::format_scale;
+use crate::lid::{self, LidPolicy};
+use crate::monitor::{Monitor, MonitorConfig, MonitorMode, OutputOptions, WorkspaceRule, WORKSPACES};
+
+const IPC_MAGIC: &[u8; 6] = b"i3-ipc";
+const RUN_COMMAND: u32 = 0;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+/// Event types have the high bit set; `output` is event 1 (7 is `tick`).
+const EVENT_OUTPUT: u32 = 0x8000_0001;
+
+const HEADER: &str = "# Outputs. disp-center rewrites the `output` lines of the outputs it\n\
+                      # configures, the output of the workspaces it assigns and its lid switch\n\
+                      # bindings, and leaves every other line alone.\n";
+
+/// Header written by versions that overwrote the whole file.
+const OLD_HEADER: &str = "# Generated by disp-center";
+
+fn write_message(stream: &mut UnixStream, msg_type: u32, payload: &str) -> Result<(), String> {
+    let mut msg = Vec::with_capacity(14 + payload.len());
+    msg.extend_from_slice(IPC_MAGIC);
+    msg.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    msg.extend_from_slice(&msg_type.to_ne_bytes());
+    msg.extend_from_slice(payload.as_bytes());
+    stream
+        .write_all(&msg)
+        .map_err(|e| format!("Failed to write to sway IPC: {e}"))
+}
+
+/// Read one reply or event: its type and JSON payload.
+fn read_message(stream: &mut UnixStream) -> Result<(u32, String), String> {
+    let mut header = [0u8; 14];
+    stream
+        .read_exact(&mut header)
+        .map_err(|e| format!("Failed to read sway IPC reply: {e}"))?;
+    if &header[..6] != IPC_MAGIC {
+        return Err("Invalid sway IPC reply".into());
+    }
+    let len = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+    let msg_type = u32::from_ne_bytes([header[10], header[11], header[12], header[13]]);
+    let mut body = vec![0u8; len];
+    stream
+        .read_exact(&mut body)
+        .map_err(|e| format!("Failed to read sway IPC reply: {e}"))?;
+
+    let body = String::from_utf8(body).map_err(|e| format!("Invalid sway IPC reply: {e}"))?;
+    Ok((msg_type, body))
+}
+
+/// Backend for Sway (and other compositors speaking the i3 IPC protocol).
+pub struct SwayBackend {
+    socket: PathBuf,
+}
+
+impl SwayBackend {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self { socket: socket.into() }
+    }
+
+    /// Send one message and return the JSON reply payload.
+    fn ipc(&self, msg_type: u32, payload: &str) -> Result<String, String> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .map_err(|e| format!("Failed to connect to {}: {e}", self.socket.display()))?;
+        write_message(&mut stream, msg_type, payload)?;
+        read_message(&mut stream).map(|(_, body)| body)
+    }
+
+    /// Run `;`-separated sway commands and fail on the first error.
+    fn command(&self, cmd: &str) -> Result<(), String> {
+        let reply = self.ipc(RUN_COMMAND, cmd)?;
+        let results: Vec<CommandResult> = serde_json::from_str(&reply)
+            .map_err(|e| format!("Failed to parse sway reply: {e}"))?;
+        match results.into_iter().find(|r| !r.success) {
+            Some(r) => Err(format!(
+                "sway '{cmd}' failed: {}",
+                r.error.unwrap_or_else(|| "unknown error".into())
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandResult {
+    success: bool,
+    error: Option<String>,
+}
+
+/// Raw JSON shape from GET_OUTPUTS.
+#[derive(Debug, Deserialize)]
+struct SwayOutput {
+    name: String,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    active: bool,
+    #[serde(default = "default_true")]
+    power: bool,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    #[serde(default)]
+    transform: String,
+    rect: SwayRect,
+    #[serde(default)]
+    modes: Vec<SwayMode>,
+    current_mode: Option<SwayMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayRect {
+    x: i32,
+    y: i32,
+}
+
+/// Sway reports refresh rates in mHz.
+#[derive(Debug, Deserialize)]
+struct SwayMode {
+    width: i32,
+    height: i32,
+    refresh: i32,
+}
+
+impl SwayMode {
+    fn to_mode(&self) -> MonitorMode {
+        MonitorMode {
+            width: self.width,
+            height: self.height,
+            refresh_rate: self.refresh as f64 / 1000.0,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// Sway transform names, indexed like Hyprland's transform values.
+const TRANSFORMS: [&str; 8] = [
+    "normal", "90", "180", "270", "flipped", "flipped-90", "flipped-180", "flipped-270",
+];
+
+fn parse_transform(s: &str) -> i32 {
+    TRANSFORMS.iter().position(|t| *t == s).unwrap_or(0) as i32
+}
+
+/// One `output` command for a monitor config (without the `output` keyword
+/// so it can be used both over IPC and in the config file).
+/// Sway can't mirror outputs; a mirrored output is placed on top of its
+/// target, which shows the same region of the desktop.
+fn output_args(c: &MonitorConfig, configs: &[MonitorConfig]) -> String {
+    if !c.enabled {
+        return format!("{} disable", c.name);
+    }
+    let (x, y) = c
+        .mirror
+        .as_ref()
+        .and_then(|target| configs.iter().find(|t| &t.name == target))
+        .map_or((c.x, c.y), |t| (t.x, t.y));
+    let mode = match &c.modeline {
+        Some(modeline) => format!("modeline {}", modeline.timings()),
+        None => format!("mode {}x{}@{:.3}Hz", c.width, c.height, c.refresh_rate),
+    };
+    format!(
+        "{} enable {mode} pos {} {} scale {} transform {}",
+        c.name, x, y, format_scale(c.scale),
+        TRANSFORMS.get(c.transform as usize).unwrap_or(&"normal"),
+    )
+}
+
+/// `~/.config/sway/outputs.conf`, written by `persist`.
+fn outputs_conf_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.config"))
+        .join("sway")
+        .join("outputs.conf")
+}
+
+/// `bindswitch` commands (without the keyword) that turn `output` off when
+/// the lid closes and back on when it opens. `enable` restores the output's
+/// settings from its `output` line.
+fn lid_bindings(output: &str) -> [String; 2] {
+    [
+        format!("--reload --locked lid:on output {output} disable"),
+        format!("--reload --locked lid:off output {output} enable"),
+    ]
+}
+
+/// Whether a config line is one of the `bindswitch` lines from `lid_bindings`.
+fn is_lid_binding(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    words.next() == Some("bindswitch")
+        && words.any(|w| w.starts_with("lid:"))
+        && words.next() == Some("output")
+}
+
+/// Config sway falls back to when the user has none of their own.
+const SYSTEM_CONFIG: &str = "/etc/sway/config";
+
+/// The user's main config in the order sway looks for it. Without one sway
+/// runs `SYSTEM_CONFIG`, which is copied to ~/.config/sway/config (as its
+/// man page suggests) so the include has somewhere to go.
+fn main_config_path(sway_dir: &std::path::Path) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().map(|h| h.join(".sway").join("config"));
+    let user = sway_dir.join("config");
+    if let Some(existing) = home.into_iter().chain([user.clone()]).find(|p| p.exists()) {
+        return Ok(existing);
+    }
+    let system = std::fs::read_to_string(SYSTEM_CONFIG).map_err(|e| {
+        format!(
+            "Failed to read {SYSTEM_CONFIG} ({e}); add `include ~/.config/sway/outputs.conf` \
+             to your sway config"
+        )
+    })?;
+    hyprconf::replace(&user, |_| system)?;
+    Ok(user)
+}
+
+/// Connector named by an `output NAME ...` line (or block); `*` and
+/// `"Make Model Serial"` rules are not ours.
+fn output_rule_name(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("output") {
+        return None;
+    }
+    words.next().filter(|name| *name != "*" && !name.starts_with('"') && !name.starts_with('{'))
+}
+
+/// Workspace number of a `workspace N output ...` assignment for one of
+/// the managed workspaces, and the outputs it lists.
+fn workspace_assignment(line: &str) -> Option<(i32, Vec<&str>)> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("workspace") {
+        return None;
+    }
+    let id: i32 = words.next()?.parse().ok()?;
+    if words.next() != Some("output") || !(1..=WORKSPACES).contains(&id) {
+        return None;
+    }
+    Some((id, words.collect()))
+}
+
+/// Rewrite the `output` lines for `configs` and the assignments of the
+/// workspaces in `workspaces` in `existing`, keeping every other line byte
+/// for byte. Each output's first line (or `{ }` block) is replaced in
+/// place; later ones are dropped. A listed workspace gets its output (or
+/// loses its assignment for an empty one); an assignment already naming
+/// that output is left alone. Missing lines are added after the last
+/// `output` or `workspace` line (or before the lid bindings).
+fn update(existing: &str, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut written: Vec<&str> = Vec::new();
+    let mut written_ws: Vec<i32> = Vec::new();
+    let mut insert_at: Option<usize> = None;
+    let mut ws_insert_at: Option<usize> = None;
+    let mut lid_at: Option<usize> = None;
+    let mut in_block = false;
+
+    for (i, line) in existing.lines().enumerate() {
+        if i == 0 && line.starts_with(OLD_HEADER) {
+            out.extend(HEADER.lines().map(str::to_string));
+            continue;
+        }
+        if in_block {
+            in_block = !line.trim_start().starts_with('}');
+            continue;
+        }
+        if let Some((id, outputs)) = workspace_assignment(line) {
+            let Some(rule) = workspaces.iter().find(|w| w.workspace == id) else {
+                out.push(line.to_string());
+                ws_insert_at = Some(out.len());
+                continue;
+            };
+            if !written_ws.contains(&id) && !rule.monitor.is_empty() {
+                out.push(if outputs == [rule.monitor.as_str()] {
+                    line.to_string()
+                } else {
+                    format!("workspace {id} output {}", rule.monitor)
+                });
+            }
+            written_ws.push(id);
+            ws_insert_at = Some(out.len());
+            continue;
+        }
+        let Some(name) = output_rule_name(line) else {
+            if lid_at.is_none() && is_lid_binding(line) {
+                lid_at = Some(out.len());
+            }
+            out.push(line.to_string());
+            continue;
+        };
+        let Some(config) = configs.iter().find(|c| c.name == name) else {
+            out.push(line.to_string());
+            insert_at = Some(out.len());
+            continue;
+        };
+        in_block = line.trim_end().ends_with('{');
+        if written.contains(&config.name.as_str()) {
+            continue;
+        }
+        written.push(&config.name);
+        out.push(format!("output {}", output_args(config, configs)));
+        insert_at = Some(out.len());
+    }
+
+    let mut missing: Vec<String> = configs
+        .iter()
+        .filter(|c| !written.contains(&c.name.as_str()))
+        .map(|c| format!("output {}", output_args(c, configs)))
+        .collect();
+    if out.is_empty() {
+        out.extend(HEADER.lines().map(str::to_string));
+        out.push(String::new());
+    }
+    let at = insert_at.or(lid_at).unwrap_or(out.len());
+    if insert_at.is_none() && lid_at.is_some() && !missing.is_empty() {
+        missing.push(String::new());
+    }
+    let added = missing.len();
+    out.splice(at..at, missing);
+
+    let missing_ws: Vec<String> = workspaces
+        .iter()
+        .filter(|w| !written_ws.contains(&w.workspace) && !w.monitor.is_empty())
+        .map(|w| format!("workspace {} output {}", w.workspace, w.monitor))
+        .collect();
+    let at = match ws_insert_at {
+        Some(i) if i >= at => i + added,
+        Some(i) => i,
+        // Right after the output lines; a blank line first at the end of the file.
+        None if !missing_ws.is_empty() && at + added == out.len() => {
+            out.push(String::new());
+            out.len()
+        }
+        None => at + added,
+    };
+    out.splice(at..at, missing_ws);
+
+    let mut content = out.join("\n");
+    content.push('\n');
+    content
+}
+
+/// `content` with the lid bindings for `internal` at the end, or without
+/// any.
+fn with_lid_bindings(content: &str, internal: Option<&str>) -> String {
+    let mut lines: Vec<String> = content.lines().filter(|l| !is_lid_binding(l)).map(String::from).collect();
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    if let Some(internal) = internal {
+        if lines.is_empty() {
+            lines.extend(HEADER.lines().map(str::to_string));
+        }
+        lines.push(String::new());
+        lines.extend(lid_bindings(internal).map(|b| format!("bindswitch {b}")));
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Edit outputs.conf (atomically, with backups) and make sure the main
+/// config includes it.
+fn write_outputs_conf(edit: impl FnOnce(&str) -> String) -> Result<(), String> {
+    let outputs_conf = outputs_conf_path();
+    let sway_dir = outputs_conf.parent().unwrap_or(std::path::Path::new(".")).to_path_buf();
+    hyprconf::replace(&outputs_conf, edit)?;
+
+    let main_conf = main_config_path(&sway_dir)?;
+    hyprconf::replace(&main_conf, |main| {
+        if main.lines().any(|l| l.trim_start().starts_with("include") && l.contains("outputs.conf")) {
+            return main.to_string();
+        }
+        let mut main = main.to_string();
+        if !main.is_empty() && !main.ends_with('\n') {
+            main.push('\n');
+        }
+        main.push_str("include ~/.config/sway/outputs.conf\n");
+        main
+    })
+}
+
+impl DisplayBackend for SwayBackend {
+    fn query_monitors(&self) -> Result<Vec<Monitor>, String> {
+        let json = self.ipc(GET_OUTPUTS, "")?;
+        let raw: Vec<SwayOutput> =
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse outputs JSON: {e}"))?;
+
+        let monitors = raw
+            .into_iter()
+            .enumerate()
+            .map(|(i, o)| {
+                let current = o
+                    .current_mode
+                    .as_ref()
+                    .or(o.modes.first())
+                    .map(SwayMode::to_mode)
+                    .unwrap_or(MonitorMode { width: 0, height: 0, refresh_rate: 0.0 });
+                let description = format!("{} {}", o.make, o.model).trim().to_string();
+
+                let edid = edid::for_connector(&o.name);
+
+                Monitor {
+                    id: i as i32,
+                    name: o.name,
+                    description,
+                    width: current.width,
+                    height: current.height,
+                    refresh_rate: current.refresh_rate,
+                    x: o.rect.x,
+                    y: o.rect.y,
+                    scale: o.scale,
+                    transform: parse_transform(&o.transform),
+                    enabled: o.active,
+                    dpms: o.power,
+                    focused: o.focused,
+                    mirror: None,
+                    options: OutputOptions::default(),
+                    available_modes: o.modes.iter().map(SwayMode::to_mode).collect(),
+                    custom_mode: None,
+                    edid,
+                }
+            })
+            .collect();
+
+        Ok(monitors)
+    }
+
+    fn apply(&self, configs: &[MonitorConfig]) -> Result<(), String> {
+        let batch: String = configs
+            .iter()
+            .map(|c| format!("output {}", output_args(c, configs)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        self.command(&batch)
+    }
+
+    fn persist(&self, configs: &[MonitorConfig], workspaces: &[WorkspaceRule]) -> Result<String, String> {
+        write_outputs_conf(|existing| update(existing, configs, workspaces))?;
+        Ok(outputs_conf_path().display().to_string())
+    }
+
+    fn set_primary(&self, monitor_name: &str) -> Result<(), String> {
+        // Like Hyprland, "primary" means workspace 1 lives there: assign it
+        // for the future and move it over now.
+        self.command(&format!("workspace 1 output {monitor_name}"))?;
+        self.command(&format!(
+            "workspace number 1; move workspace to output {monitor_name}"
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        "Sway"
+    }
+
+    fn supports_workspaces(&self) -> bool {
+        true
+    }
+
+    /// Sway has no IPC query for workspace assignments; read back the ones
+    /// `persist` wrote.
+    fn workspace_rules(&self) -> Result<Vec<WorkspaceRule>, String> {
+        let Ok(content) = std::fs::read_to_string(outputs_conf_path()) else {
+            return Ok(Vec::new());
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                if words.next() != Some("workspace") {
+                    return None;
+                }
+                let workspace: i32 = words.next()?.parse().ok()?;
+                if words.next() != Some("output") || !(1..=WORKSPACES).contains(&workspace) {
+                    return None;
+                }
+                Some(WorkspaceRule { workspace, monitor: words.next()?.to_string() })
+            })
+            .collect())
+    }
+
+    /// Assigns the workspaces. Sway can only move the focused workspace,
+    /// so existing ones stay where they are until they are recreated.
+    fn apply_workspaces(&self, rules: &[WorkspaceRule]) -> Result<(), String> {
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let batch: String = rules
+            .iter()
+            .map(|r| format!("workspace {} output {}", r.workspace, r.monitor))
+            .collect::<Vec<_>>()
+            .join("; ");
+        self.command(&batch)
+    }
+
+    fn supports_lid_switch(&self) -> bool {
+        true
+    }
+
+    /// `Disable` if outputs.conf binds the lid switch, else logind's policy.
+    fn lid_policy(&self) -> LidPolicy {
+        let content = std::fs::read_to_string(outputs_conf_path()).unwrap_or_default();
+        if content.lines().any(is_lid_binding) {
+            LidPolicy::Disable
+        } else {
+            lid::logind_policy()
+        }
+    }
+
+    /// Binds the lid switch live and in outputs.conf, then tells logind
+    /// whether to suspend.
+    fn set_lid_policy(&self, policy: LidPolicy, internal: &MonitorConfig) -> Result<(), String> {
+        let bind = policy == LidPolicy::Disable;
+        write_outputs_conf(|existing| with_lid_bindings(existing, bind.then_some(internal.name.as_str())))?;
+
+        // unbindswitch fails if the switch isn't bound.
+        let _ = self.command("unbindswitch lid:on; unbindswitch lid:off");
+        if bind {
+            let [off, on] = lid_bindings(&internal.name);
+            self.command(&format!("bindswitch {off}; bindswitch {on}"))?;
+        }
+        lid::set_logind_policy(policy)
+    }
+
+    /// Sway sends an `output` event for hotplug and for every output
+    /// change, including our own.
+    fn watch(&self, changed: Box<dyn Fn() + Send>) -> Result<(), String> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .map_err(|e| format!("Failed to connect to {}: {e}", self.socket.display()))?;
+        write_message(&mut stream, SUBSCRIBE, r#"["output"]"#)?;
+        let (_, reply) = read_message(&mut stream)?;
+        if !reply.contains("true") {
+            return Err(format!("sway refused the output subscription: {reply}"));
+        }
+        std::thread::spawn(move || {
+            while let Ok((msg_type, _)) = read_message(&mut stream) {
+                if msg_type == EVENT_OUTPUT {
+                    changed();
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn place_overlay(&self, title: &str, x: i32, y: i32) -> Result<(), String> {
+        self.command(&format!(
+            "[title=\"^{title}$\"] floating enable, sticky enable, move absolute position {x} {y}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An `output` event as sway sends it on hotplug (little-endian host).
+    const OUTPUT_EVENT_FRAME: &[u8] = b"i3-ipc\x18\x00\x00\x00\x01\x00\x00\x80{\"change\":\"unspecified\"}";
+
+    /// A sway socket in a temp dir that answers one message per connection
+    /// with the next of `replies`; the thread returns what it was sent.
+    fn fake_sway(test: &str, replies: &[&str]) -> (PathBuf, std::thread::JoinHandle<Vec<(u32, String)>>) {
+        let dir = std::env::temp_dir().join(format!("disp-center-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("sway-ipc.sock");
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let replies: Vec<String> = replies.iter().map(|r| r.to_string()).collect();
+        let server = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let (msg_type, payload) = read_message(&mut stream).unwrap();
+                write_message(&mut stream, msg_type, &reply).unwrap();
+                received.push((msg_type, payload));
+            }
+            let _ = std::fs::remove_dir_all(&dir);
+            received
+        });
+        (socket, server)
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let (mut a, mut b) = UnixStream::pair().unwrap();
+        write_message(&mut a, GET_OUTPUTS, "").unwrap();
+        write_message(&mut a, RUN_COMMAND, "output DP-1 scale 1.5").unwrap();
+        assert_eq!(read_message(&mut b).unwrap(), (GET_OUTPUTS, String::new()));
+        assert_eq!(read_message(&mut b).unwrap(), (RUN_COMMAND, "output DP-1 scale 1.5".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let (mut a, mut b) = UnixStream::pair().unwrap();
+        a.write_all(b"i4-ipc\x00\x00\x00\x00\x00\x00\x00\x00").unwrap();
+        assert!(read_message(&mut b).is_err());
+    }
+
+    #[test]
+    fn queries_outputs_over_socket() {
+        let outputs = r#"[{"name": "DP-1", "make": "Dell", "model": "U2720Q", "active": true,
+            "focused": true, "scale": 1.5, "transform": "90", "rect": {"x": 0, "y": 0},
+            "modes": [{"width": 3840, "height": 2160, "refresh": 60000}],
+            "current_mode": {"width": 3840, "height": 2160, "refresh": 59997}}]"#;
+        let (socket, server) = fake_sway("query", &[outputs]);
+        let monitors = SwayBackend::new(socket).query_monitors().unwrap();
+        assert_eq!(server.join().unwrap(), [(GET_OUTPUTS, String::new())]);
+
+        let m = &monitors[0];
+        assert_eq!((m.name.as_str(), m.description.as_str()), ("DP-1", "Dell U2720Q"));
+        assert_eq!((m.width, m.height, m.transform), (3840, 2160, 1));
+        assert!((m.refresh_rate - 59.997).abs() < 1e-9);
+    }
+
+    #[test]
+    fn command_reports_failures() {
+        let (socket, server) = fake_sway(
+            "command",
+            &[r#"[{"success": true}]"#, r#"[{"success": true}, {"success": false, "error": "Unknown output"}]"#],
+        );
+        let backend = SwayBackend::new(socket);
+        assert!(backend.command("output DP-1 enable").is_ok());
+        let err = backend.command("output DP-1 enable; output DP-9 enable").unwrap_err();
+        assert!(err.contains("Unknown output"), "{err}");
+        let sent = server.join().unwrap();
+        assert_eq!(sent[1], (RUN_COMMAND, "output DP-1 enable; output DP-9 enable".to_string()));
+    }
+
+    #[test]
+    fn decodes_output_event() {
+        let (mut sway, mut client) = UnixStream::pair().unwrap();
+        sway.write_all(OUTPUT_EVENT_FRAME).unwrap();
+        let (msg_type, body) = read_message(&mut client).unwrap();
+        assert_eq!(msg_type, EVENT_OUTPUT);
+        assert_eq!(body, r#"{"change":"unspecified"}"#);
+    }
+
+    fn config(name: &str, width: i32, height: i32, x: i32) -> MonitorConfig {
+        MonitorConfig::from_monitor(&Monitor {
+            name: name.into(),
+            width,
+            height,
+            refresh_rate: 60.0,
+            x,
+            scale: 1.0,
+            enabled: true,
+            ..Monitor::default()
+        })
+    }
+
+    fn ws(workspace: i32, monitor: &str) -> WorkspaceRule {
+        WorkspaceRule { workspace, monitor: monitor.into() }
+    }
+
+    #[test]
+    fn replaces_output_lines_in_place() {
+        let existing = "# Generated by disp-center -- edit freely, re-running disp-center will overwrite.\n\
+                        \n\
+                        output DP-1 enable mode 1920x1080@60.000Hz pos 0 0 scale 1 transform normal\n\
+                        output * bg ~/wall.png fill\n\
+                        output eDP-1 {\n    \
+                            mode 1920x1200@60Hz\n    \
+                            scale 1.25\n\
+                        }\n\
+                        output DP-1 disable\n\
+                        bindsym $mod+p exec disp-center\n";
+        let dp = config("DP-1", 2560, 1440, 0);
+        let edp = config("eDP-1", 1920, 1200, 2560);
+        let updated = update(existing, &[dp.clone(), edp.clone()], &[]);
+        let expected = format!(
+            "{HEADER}\noutput {}\noutput * bg ~/wall.png fill\noutput {}\nbindsym $mod+p exec disp-center\n",
+            output_args(&dp, &[]),
+            output_args(&edp, &[]),
+        );
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn adds_missing_outputs_and_workspaces() {
+        let dp = config("DP-1", 2560, 1440, 0);
+        let updated = update("", std::slice::from_ref(&dp), &[ws(1, "DP-1")]);
+        assert_eq!(updated, format!("{HEADER}\noutput {}\n\nworkspace 1 output DP-1\n", output_args(&dp, &[])));
+
+        let existing = "output HDMI-A-1 disable\nset $mod Mod4\n";
+        let updated = update(existing, std::slice::from_ref(&dp), &[]);
+        assert_eq!(updated, format!("output HDMI-A-1 disable\noutput {}\nset $mod Mod4\n", output_args(&dp, &[])));
+    }
+
+    #[test]
+    fn unlisted_workspace_lines_stay_byte_for_byte() {
+        let existing = "workspace 1 output DP-1  HDMI-A-1\n\
+                        workspace 2 output HDMI-A-1\n\
+                        workspace 3 output DP-1\n\
+                        workspace 3 output HDMI-A-1\n\
+                        workspace web output DP-1\n";
+        let updated = update(existing, &[], &[ws(2, "HDMI-A-1"), ws(3, "eDP-1")]);
+        assert_eq!(
+            updated,
+            "workspace 1 output DP-1  HDMI-A-1\n\
+             workspace 2 output HDMI-A-1\n\
+             workspace 3 output eDP-1\n\
+             workspace web output DP-1\n"
+        );
+    }
+
+    #[test]
+    fn empty_monitor_unassigns() {
+        let existing = "workspace 1 output DP-1\nworkspace 2 output DP-1\n";
+        assert_eq!(update(existing, &[], &[ws(1, ""), ws(4, "")]), "workspace 2 output DP-1\n");
+    }
+
+    #[test]
+    fn lid_bindings_survive_persist() {
+        let dp = config("DP-1", 2560, 1440, 0);
+        let bound = with_lid_bindings("", Some("eDP-1"));
+        assert!(bound.starts_with(HEADER));
+        assert!(bound.ends_with(&format!(
+            "\nbindswitch {}\nbindswitch {}\n",
+            lid_bindings("eDP-1")[0],
+            lid_bindings("eDP-1")[1],
+        )));
+        let persisted = update(&bound, std::slice::from_ref(&dp), &[]);
+        assert_eq!(persisted.lines().filter(|l| is_lid_binding(l)).count(), 2);
+        assert_eq!(with_lid_bindings(&persisted, Some("eDP-1")), persisted);
+        let unbound = with_lid_bindings(&persisted, None);
+        assert!(!unbound.contains("bindswitch"));
+        assert!(unbound.contains(&output_args(&dp, &[])));
+    }
+}
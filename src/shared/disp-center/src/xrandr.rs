@@ -0,0 +1,460 @@
+use std::cell::RefCell;
+use std::process::Command;
+
+use crate::backend::DisplayBackend;
+use crate::edid::{self, Edid};
+use crate::monitor::{Monitor, MonitorConfig, MonitorMode, OutputOptions, WorkspaceRule};
+
+/// Backend for X11 window managers (DWM, i3, ...) driven through `xrandr`.
+pub struct XrandrBackend {
+    /// Output that gets `--primary`; read from xrandr, changed by `set_primary`.
+    primary: RefCell<Option<String>>,
+}
+
+impl XrandrBackend {
+    pub fn new() -> Self {
+        Self { primary: RefCell::new(None) }
+    }
+
+    fn xrandr(&self, args: &[String]) -> Result<String, String> {
+        let output = Command::new("xrandr")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run xrandr: {e}"))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("xrandr {} failed: {stderr}", args.join(" ")));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// All outputs in one argument list so xrandr applies them together.
+    fn apply_args(&self, configs: &[MonitorConfig]) -> Vec<String> {
+        let primary = self.primary.borrow();
+        let mut args = Vec::new();
+        for c in configs {
+            args.push("--output".into());
+            args.push(c.name.clone());
+            if !c.enabled {
+                args.push("--off".into());
+                continue;
+            }
+            let fb_scale = 1.0 / c.scale;
+            match &c.modeline {
+                Some(modeline) => args.extend(["--mode".into(), modeline.name.clone()]),
+                None => args.extend([
+                    "--mode".into(),
+                    format!("{}x{}", c.width, c.height),
+                    "--rate".into(),
+                    format!("{:.2}", c.refresh_rate),
+                ]),
+            }
+            match &c.mirror {
+                Some(target) => args.extend(["--same-as".into(), target.clone()]),
+                None => args.extend(["--pos".into(), format!("{}x{}", c.x, c.y)]),
+            }
+            args.extend([
+                "--scale".into(),
+                format!("{fb_scale:.4}x{fb_scale:.4}"),
+                "--rotate".into(),
+                ROTATIONS[(c.transform % 4) as usize].into(),
+                "--reflect".into(),
+                if c.transform >= 4 { "x" } else { "normal" }.into(),
+            ]);
+            if primary.as_deref() == Some(c.name.as_str()) {
+                args.push("--primary".into());
+            }
+        }
+        args
+    }
+
+    /// `--newmode` and `--addmode` argument lists that make the custom
+    /// modes of `configs` available on their outputs.
+    fn custom_mode_args(&self, configs: &[MonitorConfig]) -> Vec<Vec<String>> {
+        configs
+            .iter()
+            .filter(|c| c.enabled)
+            .filter_map(|c| Some((c, c.modeline.as_ref()?)))
+            .flat_map(|(c, modeline)| {
+                let mut newmode = vec!["--newmode".to_string(), modeline.name.clone()];
+                newmode.extend(modeline.timings().split_whitespace().map(str::to_string));
+                [newmode, vec!["--addmode".into(), c.name.clone(), modeline.name.clone()]]
+            })
+            .collect()
+    }
+
+    /// `monitors.sh`: the xrandr calls that restore `configs`, one output
+    /// per line so the script stays readable.
+    fn script(&self, configs: &[MonitorConfig]) -> String {
+        let mut content = String::from(
+            "#!/bin/sh\n# Generated by disp-center -- run from the DWM autostart.\n\n",
+        );
+        for args in self.custom_mode_args(configs) {
+            let args: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+            content.push_str(&format!("xrandr {} 2>/dev/null\n", args.join(" ")));
+        }
+        content.push_str("xrandr");
+        for arg in self.apply_args(configs) {
+            content.push_str(if arg == "--output" { " \\\n  " } else { " " });
+            content.push_str(&shell_quote(&arg));
+        }
+        content.push('\n');
+        content
+    }
+}
+
+/// `arg` as one `sh` word: unchanged when it has no special characters,
+/// otherwise in single quotes.
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.,:+=@%/".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// xrandr rotation names for transforms 0-3; 4-7 add `--reflect x`.
+const ROTATIONS: [&str; 4] = ["normal", "left", "inverted", "right"];
+
+/// One output block from `xrandr --verbose`, before modes are resolved.
+#[derive(Default)]
+struct Output {
+    name: String,
+    connected: bool,
+    primary: bool,
+    geometry: Option<(i32, i32)>, // (x, y) when the output is enabled
+    rotation: i32,
+    scale: f64,
+    edid: String,
+    modes: Vec<MonitorMode>,
+    current: Option<usize>,
+}
+
+/// Parse `xrandr --verbose`: connected outputs with their modes, current
+/// mode, position, rotation, scale and EDID. Returns the monitors and the
+/// primary output.
+fn parse_verbose(text: &str) -> (Vec<Monitor>, Option<String>) {
+    let mut outputs: Vec<Output> = Vec::new();
+    let mut in_edid = false;
+    let mut pending_mode: Option<(bool, i32)> = None; // (current, width)
+
+    for line in text.lines() {
+        if line.starts_with("Screen ") {
+            continue;
+        }
+        // Output header: "DP-1 connected primary 2560x1440+0+0 (0x46) normal (...) 597mm x 336mm"
+        if !line.starts_with(char::is_whitespace) {
+            in_edid = false;
+            pending_mode = None;
+            outputs.push(parse_output_header(line));
+            continue;
+        }
+        let Some(out) = outputs.last_mut() else { continue };
+        let trimmed = line.trim();
+
+        if in_edid {
+            if trimmed.bytes().all(|b| b.is_ascii_hexdigit()) && !trimmed.is_empty() {
+                out.edid.push_str(trimmed);
+                continue;
+            }
+            in_edid = false;
+        }
+
+        if trimmed == "EDID:" {
+            in_edid = true;
+        } else if let Some(matrix) = trimmed.strip_prefix("Transform:") {
+            // First entry of the transform matrix is the framebuffer scale.
+            if let Some(s) = matrix.split_whitespace().next().and_then(|v| v.parse::<f64>().ok()) {
+                if s > 0.0 {
+                    out.scale = 1.0 / s;
+                }
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("h: width") {
+            if let Some((_, w)) = pending_mode.as_mut() {
+                *w = rest.split_whitespace().next().and_then(|v| v.parse().ok()).unwrap_or(0);
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("v: height") {
+            let Some((current, w)) = pending_mode.take() else { continue };
+            let h = rest.split_whitespace().next().and_then(|v| v.parse().ok()).unwrap_or(0);
+            let hz = rest
+                .split_whitespace()
+                .last()
+                .and_then(|v| v.trim_end_matches("Hz").parse().ok())
+                .unwrap_or(0.0);
+            if current {
+                out.current = Some(out.modes.len());
+            }
+            out.modes.push(MonitorMode { width: w, height: h, refresh_rate: hz });
+        } else if is_mode_line(trimmed) {
+            pending_mode = Some((trimmed.contains("*current"), 0));
+        }
+    }
+
+    let primary = outputs.iter().find(|o| o.primary).map(|o| o.name.clone());
+
+    // xrandr has no mirror flag: outputs sharing an origin (--same-as) mirror
+    // the first one placed there.
+    let mirrors: Vec<Option<String>> = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, o)| {
+            let geo = o.geometry?;
+            outputs[..i]
+                .iter()
+                .find(|e| e.connected && e.geometry == Some(geo))
+                .map(|e| e.name.clone())
+        })
+        .collect();
+
+    let monitors = outputs
+        .into_iter()
+        .zip(mirrors)
+        .filter(|(o, _)| o.connected)
+        .enumerate()
+        .map(|(i, (o, mirror))| {
+            let enabled = o.geometry.is_some();
+            let current = o
+                .current
+                .or(if o.modes.is_empty() { None } else { Some(0) })
+                .map(|idx| o.modes[idx].clone())
+                .unwrap_or(MonitorMode { width: 0, height: 0, refresh_rate: 0.0 });
+            let (x, y) = o.geometry.unwrap_or((0, 0));
+            let edid = edid::from_hex(&o.edid);
+            let description = edid.as_ref().map_or_else(|| o.name.clone(), Edid::model);
+
+            Monitor {
+                id: i as i32,
+                focused: o.primary,
+                name: o.name,
+                description,
+                width: current.width,
+                height: current.height,
+                refresh_rate: current.refresh_rate,
+                x,
+                y,
+                scale: o.scale,
+                transform: o.rotation,
+                enabled,
+                dpms: true,
+                mirror,
+                options: OutputOptions::default(),
+                available_modes: o.modes,
+                custom_mode: None,
+                edid,
+            }
+        })
+        .collect();
+
+    (monitors, primary)
+}
+
+fn parse_output_header(line: &str) -> Output {
+    let mut words = line.split_whitespace();
+    let mut out = Output {
+        name: words.next().unwrap_or_default().to_string(),
+        connected: words.next() == Some("connected"),
+        scale: 1.0,
+        ..Default::default()
+    };
+
+    // Everything before the "(normal left inverted right ...)" capability list.
+    let head = line.split(" (normal").next().unwrap_or(line);
+    let (mut reflect_x, mut reflect_y) = (false, false);
+    for word in head.split_whitespace().skip(2) {
+        match word {
+            "primary" => out.primary = true,
+            // "X axis", "Y axis" or "X and Y axis" after the rotation.
+            "X" => reflect_x = true,
+            "Y" => reflect_y = true,
+            _ if ROTATIONS.contains(&word) => {
+                out.rotation = ROTATIONS.iter().position(|r| *r == word).unwrap_or(0) as i32;
+            }
+            _ => {
+                if let Some(geo) = parse_geometry(word) {
+                    out.geometry = Some(geo);
+                }
+            }
+        }
+    }
+    // Reflecting in Y is reflecting in X and turning 180 degrees.
+    let half_turn = (out.rotation + 2) % 4;
+    out.rotation = match (reflect_x, reflect_y) {
+        (false, false) => out.rotation,
+        (true, false) => out.rotation + 4,
+        (false, true) => half_turn + 4,
+        (true, true) => half_turn,
+    };
+    out
+}
+
+/// "2560x1440+0+0" → (x, y).
+fn parse_geometry(word: &str) -> Option<(i32, i32)> {
+    let (_, pos) = word.split_once('+')?;
+    let (x, y) = pos.split_once('+')?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+
+/// Mode lines look like "2560x1440 (0x46) 241.500MHz +HSync -VSync *current +preferred".
+fn is_mode_line(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    words.next().is_some_and(|w| w.contains('x'))
+        && words.next().is_some_and(|w| w.starts_with("(0x"))
+}
+
+impl DisplayBackend for XrandrBackend {
+    fn query_monitors(&self) -> Result<Vec<Monitor>, String> {
+        let text = self.xrandr(&["--verbose".into()])?;
+        let (monitors, primary) = parse_verbose(&text);
+        *self.primary.borrow_mut() = primary;
+        Ok(monitors)
+    }
+
+    fn apply(&self, configs: &[MonitorConfig]) -> Result<(), String> {
+        // These fail once the mode exists, which is fine; a mode that
+        // really can't be added fails in the `--mode` below.
+        for args in self.custom_mode_args(configs) {
+            let _ = self.xrandr(&args);
+        }
+        self.xrandr(&self.apply_args(configs))?;
+        Ok(())
+    }
+
+    fn persist(&self, configs: &[MonitorConfig], _workspaces: &[WorkspaceRule]) -> Result<String, String> {
+        let config_dir =
+            dirs::config_dir().unwrap_or_else(|| std::path::PathBuf::from("~/.config"));
+        let smplos_dir = config_dir.join("smplos");
+        let script = smplos_dir.join("monitors.sh");
+
+        crate::hyprconf::replace(&script, |_| self.script(configs))?;
+
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {e}", script.display()))?;
+
+        Ok(script.display().to_string())
+    }
+
+    fn set_primary(&self, monitor_name: &str) -> Result<(), String> {
+        self.xrandr(&["--output".into(), monitor_name.into(), "--primary".into()])?;
+        *self.primary.borrow_mut() = Some(monitor_name.to_string());
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "X11 (xrandr)"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `xrandr --verbose` of a laptop with a rotated, scaled 4K monitor, a
+    /// flipped mirror, a disconnected port and a connected but disabled one.
+    const VERBOSE: &str = include_str!("../testdata/xrandr-verbose.txt");
+
+    fn monitor<'a>(monitors: &'a [Monitor], name: &str) -> &'a Monitor {
+        monitors.iter().find(|m| m.name == name).unwrap()
+    }
+
+    #[test]
+    fn lists_connected_outputs() {
+        let (monitors, primary) = parse_verbose(VERBOSE);
+        let names: Vec<&str> = monitors.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["eDP-1", "DP-1", "HDMI-1", "HDMI-2"]);
+        assert_eq!(primary.as_deref(), Some("eDP-1"));
+        assert!(monitor(&monitors, "eDP-1").focused);
+    }
+
+    #[test]
+    fn parses_modes_and_current_mode() {
+        let (monitors, _) = parse_verbose(VERBOSE);
+        let edp = monitor(&monitors, "eDP-1");
+        let modes: Vec<(i32, i32, f64)> =
+            edp.available_modes.iter().map(|m| (m.width, m.height, m.refresh_rate)).collect();
+        assert_eq!(modes, [(1920, 1080, 59.93), (1920, 1080, 59.96), (1280, 720, 59.86)]);
+        assert_eq!((edp.width, edp.height, edp.refresh_rate), (1920, 1080, 59.93));
+
+        let dp = monitor(&monitors, "DP-1");
+        assert_eq!((dp.width, dp.height), (3840, 2160));
+        assert_eq!((dp.x, dp.y), (1920, 0));
+        assert!((dp.scale - 1.5).abs() < 0.001);
+        assert_eq!(dp.description, "Dell U2720Q");
+    }
+
+    #[test]
+    fn parses_rotation_and_reflection() {
+        let (monitors, _) = parse_verbose(VERBOSE);
+        assert_eq!(monitor(&monitors, "eDP-1").transform, 0);
+        assert_eq!(monitor(&monitors, "DP-1").transform, 1);
+        // "X axis" reflects: the flipped variant of normal.
+        assert_eq!(monitor(&monitors, "HDMI-1").transform, 4);
+    }
+
+    #[test]
+    fn outputs_sharing_an_origin_mirror() {
+        let (monitors, _) = parse_verbose(VERBOSE);
+        assert_eq!(monitor(&monitors, "HDMI-1").mirror.as_deref(), Some("eDP-1"));
+        assert_eq!(monitor(&monitors, "eDP-1").mirror, None);
+    }
+
+    #[test]
+    fn connected_but_off_output() {
+        let (monitors, _) = parse_verbose(VERBOSE);
+        let hdmi = monitor(&monitors, "HDMI-2");
+        assert!(!hdmi.enabled);
+        assert_eq!(hdmi.available_modes.len(), 2);
+        // Without a current mode the first (preferred) one is shown.
+        assert_eq!((hdmi.width, hdmi.height), (2560, 1440));
+        assert!(hdmi.edid.is_none());
+    }
+
+    #[test]
+    fn header_fields() {
+        let out = parse_output_header("DP-3 connected 1080x1920+0+0 (0x4a) right X axis (normal left) 0mm x 0mm");
+        assert!(out.connected && !out.primary);
+        assert_eq!(out.rotation, 7);
+        assert_eq!(out.geometry, Some((0, 0)));
+
+        let out = parse_output_header("VGA-1 disconnected (normal left inverted right x axis y axis)");
+        assert!(!out.connected);
+        assert_eq!(out.geometry, None);
+        assert!(is_mode_line("2560x1440 (0x46) 241.500MHz +HSync -VSync *current +preferred"));
+        assert!(!is_mode_line("Transform:  1.000000 0.000000 0.000000"));
+    }
+
+    #[test]
+    fn y_reflection_reads_back() {
+        let header = |transform: &str| {
+            parse_output_header(&format!(
+                "DP-3 connected 1920x1080+0+0 (0x4a) {transform} (normal left inverted right x axis y axis) 0mm x 0mm"
+            ))
+            .rotation
+        };
+        assert_eq!(header("normal Y axis"), 6);
+        assert_eq!(header("inverted Y axis"), 4);
+        assert_eq!(header("left Y axis"), 7);
+        assert_eq!(header("normal X and Y axis"), 2);
+        assert_eq!(header("right X and Y axis"), 1);
+        assert_eq!(header("inverted X axis"), 6);
+    }
+
+    #[test]
+    fn script_quotes_arguments() {
+        assert_eq!(shell_quote("HDMI-1"), "HDMI-1");
+        assert_eq!(shell_quote("1920x1080_60.00"), "1920x1080_60.00");
+        assert_eq!(shell_quote("my mode"), "'my mode'");
+        assert_eq!(shell_quote("a'b;$(rm)"), r"'a'\''b;$(rm)'");
+        assert_eq!(shell_quote(""), "''");
+
+        let (monitors, _) = parse_verbose(VERBOSE);
+        let configs: Vec<MonitorConfig> = monitors.iter().map(MonitorConfig::from_monitor).collect();
+        let mut odd = configs[0].clone();
+        odd.name = "DP-1 $(reboot)".into();
+        let script = XrandrBackend::new().script(&[odd]);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(" \\\n  --output 'DP-1 $(reboot)' --mode 1920x1080"));
+    }
+}